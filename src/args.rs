@@ -16,6 +16,10 @@ pub struct Simulate {
     #[clap(short, long)]
     /// The file to simulate
     pub file: String,
+
+    #[clap(last = true)]
+    /// Arguments passed to the program. They follow after a --
+    pub args: Vec<String>,
 }
 
 #[derive(CArgs, Debug, Clone)]
@@ -23,6 +27,10 @@ pub struct Interpret {
     #[clap(short, long)]
     /// The file to interpret
     pub file: String,
+
+    #[clap(last = true)]
+    /// Arguments passed to the program. They follow after a --
+    pub args: Vec<String>,
 }
 
 #[derive(CArgs, Debug, Clone)]
@@ -66,11 +74,23 @@ pub struct Compile {
     /// Level 2: Totally stripped data
     pub strip_data: usize,
 
+    #[clap(long, default_value = "1")]
+    /// How much the program should be optimised.
+    /// Level 0: No optimisations
+    /// Level 1: Constant folding, removal of noop and removal of functions unreachable from main
+    /// Level 2: Level 1 and inlining of small functions
+    pub opt_level: u8,
+
     #[clap(short, long)]
     /// How the bytecode should be generated.
     /// If toggled the output will be generated in yaml
     pub readable: bool,
 
+    #[clap(long)]
+    /// Compiles a library (.scylib) instead of a program.
+    /// All functions which are not private are exported and can be used with include
+    pub lib: bool,
+
     #[clap(short, long)]
     /// The file to perform to compile
     pub file: String,
@@ -80,6 +100,61 @@ pub struct Compile {
     pub out_file: String,
 }
 
+#[derive(CArgs, Debug, Clone)]
+pub struct Build {
+    #[clap(short, long)]
+    /// The project folder or the path to its manifest.
+    /// Defaults to the first folder containing a *-meta.scy.yml, starting at the current folder.
+    /// Next to the metadata the manifest may contain:
+    /// entry: "ExampleProgram-main.scy"
+    /// sources:
+    ///   - "lib"
+    /// dependencies:
+    ///   logging:
+    ///     path: "../logging-scy"
+    pub path: Option<String>,
+
+    #[clap(short, long, parse(from_occurrences))]
+    /// To which level the debug symbols will be stripped.
+    /// Level 0: Full token data
+    /// Level 1: Only positional data
+    /// Level 2: Totally stripped data
+    pub strip_data: usize,
+
+    #[clap(long, default_value = "1")]
+    /// How much the program should be optimised.
+    /// Level 0: No optimisations
+    /// Level 1: Constant folding, removal of noop and removal of functions unreachable from main
+    /// Level 2: Level 1 and inlining of small functions
+    pub opt_level: u8,
+
+    #[clap(short, long)]
+    /// How the bytecode should be generated.
+    /// If toggled the output will be generated in yaml
+    pub readable: bool,
+
+    #[clap(short, long)]
+    /// The output path of the compiled program.
+    /// Defaults to target/<name>.scyc in the project folder
+    pub out_file: Option<String>,
+}
+
+#[derive(CArgs, Debug, Clone)]
+pub struct Run {
+    #[clap(short, long)]
+    /// The project folder or the path to its manifest.
+    /// Defaults to the first folder containing a *-meta.scy.yml, starting at the current folder
+    pub path: Option<String>,
+
+    #[clap(long, default_value = "1")]
+    /// How much the program should be optimised. See build
+    pub opt_level: u8,
+
+    #[clap(last = true)]
+    /// Arguments passed to the program. They follow after a --
+    pub args: Vec<String>,
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Action {
     /// Simulates the program
@@ -87,6 +162,11 @@ pub enum Action {
     /// Compiles the program into bytecode.
     /// Here you need the optional parameters: <out-file>,
     Compile(Compile),
+    /// Compiles a project using its manifest.
+    /// Unchanged projects are not compiled again
+    Build(Build),
+    /// Builds and runs a project
+    Run(Run),
     /// Interpret the byte code
     Interpret(Interpret),
     /// Dump the metadata of the program
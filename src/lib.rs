@@ -0,0 +1,19 @@
+#![feature(trusted_random_access)]
+#![feature(once_cell)]
+#![feature(fn_traits)]
+#![feature(trivial_bounds)]
+
+//! The Stackyy compiler and virtual machine. The scy binary is built on top of it,
+//! embedders can use it to run programs with their own descriptors
+
+use crate::util::{compiler_error, compiler_error_str};
+use crate::util::compile::CompiledProgram;
+use crate::util::operation::OperationDataInfo;
+use crate::util::position::Position;
+use crate::vm::VM;
+
+pub mod args;
+pub mod parser;
+pub mod util;
+pub mod vm;
+pub mod opt;
@@ -1,32 +1,23 @@
 #![feature(panic_info_message)]
-#![feature(trusted_random_access)]
-#![feature(once_cell)]
-#![feature(fn_traits)]
-#![feature(trivial_bounds)]
 #![feature(path_try_exists)]
 
 use std::fs;
 use std::fs::OpenOptions;
 use std::io::{Read, stdin, stdout, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 
 use backtrace::Backtrace;
 use clap::Parser;
 
-use crate::args::{Action, Args};
-use crate::parser::{pre_parse, tokenize};
-use crate::util::{compiler_error, compiler_error_str};
-use crate::util::compile::{CompiledProgram, ProgramMetadata};
-use crate::util::operation::OperationDataInfo;
-use crate::util::position::Position;
-use crate::vm::VM;
-
-pub mod args;
-pub mod parser;
-pub mod util;
-pub mod vm;
-pub mod opt;
+use stackyy::args::{Action, Args, Build, Compile};
+use stackyy::parser::{pre_parse, tokenize};
+use stackyy::util::{compiler_error, compiler_error_str};
+use stackyy::util::compile::{CompiledProgram, ProgramMetadata};
+use stackyy::util::operation::OperationDataInfo;
+use stackyy::util::cache::ParseCache;
+use stackyy::util::project::{BuildStamp, IncludeResolver, Project, ProjectManifest};
+use stackyy::vm::VM;
 
 fn main() {
     std::panic::set_hook(Box::new(|panic_info| {
@@ -71,13 +62,16 @@ fn main() {
             let file_path = PathBuf::from(&simulate_options.file);
             let path = file_path.clone().parent().unwrap().to_path_buf();
 
-            let pre_parsed = pre_parse(file_text, file_path, path.clone());
-            let parsed = tokenize(pre_parsed, 0, path, None);
+            let pre_parsed = pre_parse(file_text, file_path.clone(), path.clone());
+            let cache = ParseCache::new(Project::cache_dir_of(&file_path), &IncludeResolver::default());
+            let parsed = tokenize(pre_parsed, file_path, path, None, IncludeResolver::default(), Some(cache));
 
             let checked = parsed.type_check();
 
             if checked.is_ok() {
-                checked.unwrap().run();
+                let mut vm = checked.unwrap();
+                vm.set_args(simulate_options.args);
+                vm.run();
             } else {
                 let error = checked.err().unwrap();
                 compiler_error(format!("Type check failed:\r\n\t{}", error), &OperationDataInfo::None);
@@ -131,35 +125,55 @@ fn main() {
             let file_path = PathBuf::from(compiler_options.file.clone());
             let path = file_path.clone().parent().unwrap().to_path_buf();
 
-            let pre_parsed = pre_parse(file_text, file_path, path.clone());
-            let parsed = tokenize(pre_parsed, 0, path, Some(compiler_options.clone()));
+            let pre_parsed = pre_parse(file_text, file_path.clone(), path.clone());
+            let cache = ParseCache::new(Project::cache_dir_of(&file_path), &IncludeResolver::default());
+            let parsed = tokenize(pre_parsed, file_path, path, Some(compiler_options.clone()), IncludeResolver::default(), Some(cache));
 
             let meta = serde_yaml::from_str(&meta);
             if meta.is_err() {
                 compiler_error_str("Your meta is invalid", &OperationDataInfo::None);
             }
 
-            let byte_code = parsed.compile(meta.unwrap(), *&compiler_options.readable);
+            let byte_code = if compiler_options.lib {
+                parsed.compile_library(meta.unwrap(), *&compiler_options.readable, compiler_options.opt_level)
+            } else {
+                parsed.compile(meta.unwrap(), *&compiler_options.readable, compiler_options.opt_level)
+            };
             if byte_code.is_err() {
                 compiler_error_str("Could not compile into bytecode", &OperationDataInfo::None);
             }
 
             let byte_code = byte_code.unwrap();
 
-            let file_path = compiler_options.out_file.clone();
-            let file_path = PathBuf::from(file_path);
-            let file = OpenOptions::new().write(true).truncate(true).create(true).open(file_path);
-            if file.is_err() {
-                compiler_error_str("Could not open file", &OperationDataInfo::None);
-            }
+            write_program(&byte_code, PathBuf::from(compiler_options.out_file.clone()));
+
+            println!("Sucessfully compiled file");
+        }
+        Action::Build(build_options) => {
+            build_project(&build_options);
+        }
+        Action::Run(run_options) => {
+            let out_file = build_project(&Build {
+                path: run_options.path.clone(),
+                strip_data: 0,
+                opt_level: run_options.opt_level,
+                readable: false,
+                out_file: None,
+            });
+
+            let file_bytes = fs::read(&out_file).unwrap_or_else(|err| {
+                eprintln!("Could not read file {:?}: {}", out_file, err);
+                exit(1);
+            });
 
-            let mut file = file.unwrap();
-            let success = file.write_all(&byte_code);
-            if success.is_err() {
-                compiler_error_str("Could not write file", &OperationDataInfo::None);
+            let compiled_program = bincode::deserialize::<CompiledProgram>(&file_bytes);
+            if compiled_program.is_err() {
+                compiler_error(format!("Could not program from file. {}", compiled_program.err().unwrap()), &OperationDataInfo::None);
             }
 
-            println!("Sucessfully compiled file");
+            let mut vm = VM::from(compiled_program.unwrap());
+            vm.set_args(run_options.args);
+            vm.run();
         }
         Action::Interpret(interpreter_options) => {
             let file_bytes = {
@@ -191,6 +205,7 @@ fn main() {
 
             let compiled_program = compiled_program.unwrap();
             let mut vm = VM::from(compiled_program);
+            vm.set_args(interpreter_options.args);
             vm.run();
         }
         Action::Info(info_options) => {
@@ -269,12 +284,12 @@ fn main() {
             let mut meta_path = pkg_root.clone();
             meta_path.push(format!("{}-meta.scy.yml", new_options.name.clone()));
 
-            let example_meta = ProgramMetadata {
+            let example_meta = ProjectManifest::new(ProgramMetadata {
                 name: new_options.name.clone(),
                 version: "1.0".to_string(),
                 author: None,
                 description: None
-            };
+            });
             let file_data = serde_yaml::to_string(&example_meta).expect("metadata to be serialized");
 
             let mut file = OpenOptions::new().write(true).truncate(true).create(true).open(meta_path).expect("metadata to be written");
@@ -296,3 +311,79 @@ end"#, new_options.name.clone(), new_options.name.clone());
         }
     }
 }
+
+fn write_program(byte_code: &Vec<u8>, file_path: PathBuf) {
+    let file = OpenOptions::new().write(true).truncate(true).create(true).open(file_path);
+    if file.is_err() {
+        compiler_error_str("Could not open file", &OperationDataInfo::None);
+    }
+
+    let mut file = file.unwrap();
+    let success = file.write_all(byte_code);
+    if success.is_err() {
+        compiler_error_str("Could not write file", &OperationDataInfo::None);
+    }
+}
+
+/// Compiles the project found from the build options and returns the path of the compiled program
+fn build_project(build_options: &Build) -> PathBuf {
+    let project = match &build_options.path {
+        Some(path) => Project::load(Path::new(path)),
+        None => Project::find(&std::env::current_dir().expect("current directory to be accessible")),
+    };
+    let name = project.manifest().data.name.clone();
+
+    let out_file = build_options.out_file.clone()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| project.output());
+
+    let options = format!("strip: {} opt: {} readable: {}", build_options.strip_data, build_options.opt_level, build_options.readable);
+    if BuildStamp::is_fresh(&out_file, &options) {
+        // Status messages go to stderr, so they do not mix with the output of programs started by run
+        eprintln!("Project {} is up to date", name);
+        return out_file;
+    }
+
+    let file_path = project.entry();
+    let file_text = fs::read_to_string(&file_path).unwrap_or_else(|err| {
+        eprintln!("Could not read file {:?}: {}", file_path, err);
+        exit(1);
+    });
+
+    let compiler_options = Compile {
+        meta_path: project.manifest_path().to_string_lossy().to_string(),
+        strip_data: build_options.strip_data,
+        opt_level: build_options.opt_level,
+        readable: build_options.readable,
+        lib: false,
+        file: file_path.to_string_lossy().to_string(),
+        out_file: out_file.to_string_lossy().to_string(),
+    };
+
+    let path = file_path.clone().parent().unwrap().to_path_buf();
+
+    let pre_parsed = pre_parse(file_text, file_path.clone(), path.clone());
+    let resolver = project.resolver();
+    let cache = ParseCache::new(project.cache_dir(), &resolver);
+    let parsed = tokenize(pre_parsed, file_path, path, Some(compiler_options), resolver, Some(cache));
+
+    let mut sources = parsed.source_files();
+    sources.push(project.manifest_path().clone());
+
+    let byte_code = parsed.compile(project.manifest().data.clone(), build_options.readable, build_options.opt_level);
+    if byte_code.is_err() {
+        compiler_error_str("Could not compile into bytecode", &OperationDataInfo::None);
+    }
+
+    if let Some(parent) = out_file.parent() {
+        if fs::create_dir_all(parent).is_err() {
+            compiler_error(format!("Could not create the folder {:?}", parent), &OperationDataInfo::None);
+        }
+    }
+
+    write_program(&byte_code.unwrap(), out_file.clone());
+    BuildStamp::new(sources, options).write(&out_file);
+
+    eprintln!("Successfully built project {}", name);
+    out_file
+}
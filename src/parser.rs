@@ -1,4 +1,5 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::fs::OpenOptions;
 use std::io::Read;
 use std::iter::TrustedRandomAccessNoCoerce;
@@ -11,25 +12,55 @@ use serde::{Deserialize, Serialize};
 use crate::args::Compile;
 use crate::opt::resolve_opt;
 use crate::util::{compiler_error, compiler_error_str, compiler_warning};
-use crate::util::compile::{CompiledFunction, CompiledProgram, ProgramMetadata};
+use crate::util::cache::{CachedState, ParseCache};
+use crate::util::compile::{CompiledFunction, CompiledLibrary, CompiledProgram, LIBRARY_EXTENSION, ProgramMetadata};
 use crate::util::internals::{Internal, to_internal};
-use crate::util::operation::{Operand, Operation, OperationData, OperationDataInfo, OperationType};
-use crate::util::operations::{CALLING_RUNTIME, CALLING_TYPECHECK, DESCRIPTOR_RUNTIME, DESCRIPTOR_TYPECHECK, INTERNAL_RUNTIME, INTERNAL_TYPECHECK, SIMPLE_RUNTIME, SIMPLE_TYPECHECK};
+use crate::util::operation::{Operand, Operation, OperationData, OperationDataInfo, OperationType, TypeFrame};
+use crate::util::optimize::optimize;
+use crate::util::operations::{CALLING_RUNTIME, CALLING_TYPECHECK, DESCRIPTOR_RUNTIME, DESCRIPTOR_TYPECHECK, GLOBAL_RUNTIME, GLOBAL_TYPECHECK, INTERNAL_RUNTIME, INTERNAL_TYPECHECK, LOCAL_RUNTIME, LOCAL_TYPECHECK, SIMPLE_RUNTIME, SIMPLE_TYPECHECK, STRUCT_RUNTIME, STRUCT_TYPECHECK};
 use crate::util::position::Position;
+use crate::util::project::IncludeResolver;
+use crate::util::register_type::RegisterType;
 use crate::util::token::*;
 use crate::util::token::TokenType::Function as TokenFunction;
-use crate::util::type_check::{ErrorTypes, TypeCheckError, Types};
+use crate::util::type_check::{TypeCheckError, Types};
 use crate::VM;
-use crate::vm::MAX_CALL_STACK_SIZE;
-
-static MAX_INCL_DEPTH: u8 = 3;
+use crate::vm::{MAX_CALL_STACK_SIZE, REGISTER_COUNT};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FunctionData(String, Vec<Types>, Vec<Types>);
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StructData(pub(crate) String, pub(crate) Vec<(String, Types)>);
+
+impl StructData {
+    pub fn name(&self) -> String {
+        self.0.clone()
+    }
+
+    pub fn fields(&self) -> &Vec<(String, Types)> {
+        &self.1
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct VariableData(pub(crate) String, pub(crate) Types, pub(crate) Operand);
+
+impl VariableData {
+    pub fn typ(&self) -> &Types {
+        &self.1
+    }
+
+    pub fn initial(&self) -> &Operand {
+        &self.2
+    }
+}
+
 #[derive(Clone)]
 pub struct Function {
     pub(crate) data: FunctionData,
+    /// The registers declared local. They are saved before a call and restored after it
+    pub(crate) local_registers: Vec<usize>,
     pub(crate) operations: Vec<(OperationDataInfo, Operation)>,
 }
 
@@ -39,23 +70,16 @@ impl Function {
     }
 
 
-    pub fn type_check(&self, functions: HashMap<String, Function>, stack: &mut Vec<Types>) -> TypeCheckError {
-        self.operations.iter()
-            .fold(ErrorTypes::None.into(), |acc, op| {
-                let type_check: TypeCheckError = op.1.type_check(&functions, stack, true).into();
-
-                if type_check.is_error() {
-                    let op = op.clone();
-                    let data = op.1.data();
-                    let typ = &data.typ;
-                    let operand = &data.operand;
-                    let pos = &data.data;
-                    compiler_warning(format!("\r\nOperation caused type check failure. \r\nOperation Type: {:?} \r\nOperation Value: {:?}", typ, operand), pos);
-                    type_check
-                } else {
-                    acc
+    /// The registers the function stores or empties without declaring them local
+    fn changed_registers(&self) -> Vec<usize> {
+        self.operations.iter().filter_map(|(_, op)| {
+            match &op.data.operand {
+                Some(Operand::Internal(internal @ (Internal::RegisterStore(_) | Internal::RegisterLoad(_)))) => {
+                    internal.register().filter(|register| !self.local_registers.contains(register))
                 }
-            })
+                _ => None,
+            }
+        }).collect()
     }
 
     pub fn name(&self) -> String {
@@ -66,35 +90,499 @@ impl Function {
     }
 }
 
+/// The value and type of an int, str or bool literal. Bools are written as true and false
+fn literal(token: &Token) -> Option<(Operand, Types)> {
+    match (token.typ(), token.value()) {
+        (TokenType::Int, TokenValue::Int(int)) => Some((Operand::Int(*int), Types::Int)),
+        (TokenType::Str, TokenValue::String(str)) => Some((Operand::Str(str.clone()), Types::String)),
+        (TokenType::Word, _) if token.text() == "true" || token.text() == "false" => Some((Operand::Bool(token.text() == "true"), Types::Bool)),
+        _ => None,
+    }
+}
+
+/// The index of the register a to h with the given name
+fn register_index(name: &str) -> Option<usize> {
+    match name.as_bytes() {
+        [letter] if (b'a'..b'a' + REGISTER_COUNT as u8).contains(letter) => Some((letter - b'a') as usize),
+        _ => None,
+    }
+}
+
+/// Type checks functions in the context of their calls.
+/// Registers which are not local keep their value across calls, so a called function is checked
+/// with the register types of its caller and the caller continues with the register types it leaves
+struct RegisterContext<'a> {
+    functions: &'a HashMap<String, Function>,
+    /// The register types each function was checked with and the register types it leaves
+    checked: HashMap<String, Vec<(Vec<Option<Types>>, Vec<Option<Types>>)>>,
+    chain: Vec<String>,
+}
+
+impl<'a> RegisterContext<'a> {
+    fn new(functions: &'a HashMap<String, Function>) -> Self {
+        RegisterContext { functions, checked: HashMap::new(), chain: vec![] }
+    }
+
+    /// The global registers the function or any function it calls directly or indirectly can change
+    fn changed_registers(&self, name: &str) -> HashSet<usize> {
+        let mut visited = HashSet::new();
+        let mut open = vec![name.to_string()];
+        let mut registers = HashSet::new();
+
+        while let Some(name) = open.pop() {
+            if !visited.insert(name.clone()) {
+                continue;
+            }
+
+            if let Some(function) = self.functions.get(&name) {
+                registers.extend(function.changed_registers());
+                open.extend(function.operations.iter().filter_map(|(_, op)| {
+                    if let Some(Operand::Call(callee)) = &op.data.operand { Some(callee.clone()) } else { None }
+                }));
+            }
+        }
+
+        registers
+    }
+
+    /// The callee of a call through a function pointer is not known. It may change any global register
+    fn dynamic_call(&self, op: &Operation) -> bool {
+        match (&op.data.typ, &op.data.operand) {
+            (OperationType::Call | OperationType::CallIf, None) => true,
+            (_, Some(Operand::Internal(Internal::MapForEach))) => true,
+            _ => false,
+        }
+    }
+
+    /// Type checks the function with the given register types. Returns the register types it leaves
+    fn check(&mut self, name: &str, registers: Vec<Option<Types>>) -> Result<Vec<Option<Types>>, String> {
+        let function = match self.functions.get(name) {
+            Some(function) => function,
+            None => return Ok(registers),
+        };
+
+        if let Some((_, left)) = self.checked.get(name).and_then(|checked| checked.iter().find(|(entry, _)| *entry == registers)) {
+            return Ok(left.clone());
+        }
+
+        // The result of a recursive call is not known yet. The registers it can change are unknown afterwards
+        if self.chain.iter().any(|caller| caller == name) {
+            let changed = self.changed_registers(name);
+            return Ok(registers.into_iter().enumerate().map(|(index, typ)| {
+                if changed.contains(&index) && !function.local_registers.contains(&index) { None } else { typ }
+            }).collect());
+        }
+
+        self.chain.push(name.to_string());
+
+        let mut stack = function.get_contract().0;
+        let mut frame = TypeFrame::new();
+        frame.registers = registers.clone();
+        for register in &function.local_registers {
+            frame.registers[*register] = None;
+        }
+
+        let mut error: Option<TypeCheckError> = None;
+        for (_, op) in &function.operations {
+            let type_check = op.type_check_with_frame(self.functions, &mut stack, &mut frame, true);
+
+            if type_check.is_error() {
+                let data = op.data();
+                compiler_warning(format!("\r\nOperation caused type check failure. \r\nOperation Type: {:?} \r\nOperation Value: {:?}", data.typ, data.operand), &data.data);
+                // Later failures are usually caused by the first one
+                error.get_or_insert(type_check);
+            } else if let (OperationType::Call, Some(Operand::Call(callee))) = (&op.data.typ, &op.data.operand) {
+                frame.registers = self.check(callee, frame.registers)?;
+            } else if self.dynamic_call(op) {
+                let changed = self.functions.values().flat_map(|function| function.changed_registers()).collect::<HashSet<_>>();
+                for register in changed {
+                    frame.registers[register] = None;
+                }
+            }
+        }
+
+        if let Some(error) = error {
+            return Err(format!("Function {} failed type check: {}", name, error));
+        }
+
+        let outp = function.get_contract().1;
+        if stack.len() != outp.len() || !outp.iter().zip(&stack).all(|(wanted, got)| wanted.accepts(got)) {
+            return Err(format!("Function {} failed type check! You still have unused elements left. Elements are: {:?}", name, stack));
+        }
+
+        // Local registers are restored for the caller
+        let mut left = frame.registers;
+        for register in &function.local_registers {
+            left[*register] = registers[*register].clone();
+        }
+
+        self.chain.pop();
+        self.checked.entry(name.to_string()).or_default().push((registers, left.clone()));
+        Ok(left)
+    }
+}
+
+/// Every file reached through includes, keyed by its canonical path
+#[derive(Default, Clone)]
+pub struct IncludeGraph {
+    edges: HashMap<PathBuf, Vec<PathBuf>>,
+    parsed: HashMap<PathBuf, State>,
+    chain: Vec<PathBuf>,
+    resolver: IncludeResolver,
+    cache: Option<ParseCache>,
+}
+
+impl IncludeGraph {
+    /// The edges of all files reachable from the given file
+    fn subgraph(&self, file: &PathBuf) -> HashMap<PathBuf, Vec<PathBuf>> {
+        let mut subgraph = HashMap::new();
+        let mut open = vec![file.clone()];
+
+        while let Some(file) = open.pop() {
+            if subgraph.contains_key(&file) {
+                continue;
+            }
+
+            let edges = self.edges.get(&file).cloned().unwrap_or_default();
+            open.extend(edges.clone());
+            subgraph.insert(file, edges);
+        }
+
+        subgraph
+    }
+}
+
+#[derive(Clone)]
 pub struct State {
     operations: HashMap<String, Function>,
     functions: HashMap<String, (Vec<Types>, Vec<Types>)>,
-    incl_lvl: u8,
+    structs: HashMap<String, StructData>,
+    constants: HashMap<String, Operand>,
+    variables: HashMap<String, VariableData>,
+    file: PathBuf,
     path: PathBuf,
     sys_libs: Vec<String>,
     in_fn: Option<Function>,
+    /// The names bound by each open let and the position of the let
+    locals: Vec<(OperationDataInfo, Vec<String>)>,
+    /// The position of the let closed last in the current function
+    closed_let: Option<OperationDataInfo>,
+    private: HashSet<String>,
+    origins: HashMap<String, PathBuf>,
+    includes: IncludeGraph,
 }
 
 impl State {
-    pub fn new(path: PathBuf) -> Self {
+    pub fn new(file: PathBuf, path: PathBuf) -> Self {
         Self {
             operations: HashMap::new(),
             functions: HashMap::new(),
-            incl_lvl: 0,
+            structs: HashMap::new(),
+            constants: HashMap::new(),
+            variables: HashMap::new(),
             in_fn: None,
             sys_libs: vec![],
+            locals: vec![],
+            closed_let: None,
+            private: HashSet::new(),
+            origins: HashMap::new(),
+            includes: IncludeGraph::default(),
+            file,
             path,
         }
     }
 
-    pub fn new_with_include(old: u8, path: PathBuf) -> Self {
-        Self {
-            operations: HashMap::new(),
-            functions: HashMap::new(),
-            incl_lvl: old + 1,
-            in_fn: None,
-            sys_libs: vec![],
-            path,
+    /// All files this state was parsed from, including every included file
+    pub fn source_files(&self) -> Vec<PathBuf> {
+        self.includes.subgraph(&self.file).into_keys()
+            .filter(|file| file.is_file())
+            .collect()
+    }
+
+    fn to_cache(&self) -> CachedState {
+        CachedState {
+            operations: self.operations.iter().map(|(name, function)| (name.clone(), CompiledFunction::from(function))).collect(),
+            functions: self.functions.clone(),
+            structs: self.structs.clone(),
+            constants: self.constants.clone(),
+            variables: self.variables.clone(),
+            sys_libs: self.sys_libs.clone(),
+            private: self.private.clone(),
+            origins: self.origins.clone(),
+        }
+    }
+
+    fn from_cache(file: PathBuf, path: PathBuf, cached: CachedState) -> Self {
+        let mut state = State::new(file, path);
+        state.operations = cached.operations.into_iter().map(|(name, function)| (name, Function::from(function))).collect();
+        state.functions = cached.functions;
+        state.structs = cached.structs;
+        state.constants = cached.constants;
+        state.variables = cached.variables;
+        state.sys_libs = cached.sys_libs;
+        state.private = cached.private;
+        state.origins = cached.origins;
+        state
+    }
+
+    /// Loads a compiled library. Only its exported functions can be called
+    fn include_library(&mut self, file: PathBuf, info: &OperationDataInfo) -> State {
+        let edges = self.includes.edges.entry(self.file.clone()).or_insert(vec![]);
+        if !edges.contains(&file) {
+            edges.push(file.clone());
+        }
+
+        let bytes = fs::read(&file).unwrap_or_else(|_| {
+            compiler_error(format!("The file {:?} could not be read from", file), info);
+        });
+
+        let library = bincode::deserialize::<CompiledLibrary>(&bytes).ok()
+            .or_else(|| serde_yaml::from_slice::<CompiledLibrary>(&bytes).ok());
+
+        if let None = library {
+            compiler_error(format!("The file {:?} is not a valid library", file), info);
+        }
+
+        let library = library.unwrap();
+
+        let mut state = State::new(file.clone(), file.parent().unwrap().to_path_buf());
+        state.private = library.operations.keys().filter(|name| !library.exports.contains_key(*name)).cloned().collect();
+        state.operations = library.operations.into_iter().map(|(name, function)| (name, Function::from(function))).collect();
+        state.functions = library.exports;
+        state.structs = library.structs;
+        state.variables = library.variables;
+        state
+    }
+
+    /// Parses an included file once and returns its state.
+    /// Files are keyed by their canonical path, so including a file a second time reuses the first result
+    fn include_file(&mut self, file: PathBuf, path: PathBuf, source: impl FnOnce() -> String, comp: &Compile, info: &OperationDataInfo) -> State {
+        if let Some(start) = self.includes.chain.iter().position(|included| included == &file) {
+            let chain = self.includes.chain[start..].iter()
+                .chain(std::iter::once(&file))
+                .map(|included| included.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            compiler_error(format!("Include cycle detected: {}", chain), info);
+        }
+
+        let edges = self.includes.edges.entry(self.file.clone()).or_insert(vec![]);
+        if !edges.contains(&file) {
+            edges.push(file.clone());
+        }
+
+        if let Some(state) = self.includes.parsed.get(&file) {
+            return state.clone();
+        }
+
+        let cached = self.includes.cache.as_ref().and_then(|cache| cache.load(&file, comp.strip_data));
+        if let Some(cached) = cached {
+            let state = State::from_cache(file.clone(), path, cached.state);
+            self.includes.edges.extend(cached.edges);
+            self.includes.parsed.insert(file, state.clone());
+            return state;
+        }
+
+        let parsed = pre_parse(source(), file.clone(), path.clone());
+
+        let mut state = State::new(file.clone(), path);
+        state.includes = std::mem::take(&mut self.includes);
+        state.includes.chain.push(file.clone());
+
+        state.update(parsed.par_iter().map(|token| {
+            let token = token.clone();
+
+            (token.clone().0, Token::from(token))
+        }).collect(), Some(comp.clone()));
+
+        self.includes = std::mem::take(&mut state.includes);
+        self.includes.chain.pop();
+        if let Some(cache) = &self.includes.cache {
+            cache.store(&file, comp.strip_data, self.includes.subgraph(&file), state.to_cache());
+        }
+
+        self.includes.parsed.insert(file, state.clone());
+
+        state
+    }
+
+    /// Merges an included file into this state.
+    /// With a module name the public functions, constants, variables and structs are only reachable as module::name.
+    /// Private functions are renamed so they can not clash and are not callable from the includer
+    fn merge_include(&mut self, state: State, alias: Option<String>, info: &OperationDataInfo) {
+        let local_names = state.operations.keys().cloned().collect::<HashSet<_>>();
+        let struct_names = state.structs.keys().cloned().collect::<HashSet<_>>();
+        let variable_names = state.variables.keys().cloned().collect::<HashSet<_>>();
+        let private = state.private;
+        let source = state.file.display().to_string();
+        // Without a module name private functions are named after the file, so the names do not depend on where it is stored
+        let module = alias.clone().unwrap_or_else(|| {
+            state.file.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().to_string())
+        });
+
+        let qualify = |name: &String| -> String {
+            if let Some(alias) = &alias {
+                format!("{}::{}", alias, name)
+            } else {
+                name.clone()
+            }
+        };
+
+        let rename = |name: &String| -> String {
+            if !local_names.contains(name) {
+                name.clone()
+            } else if private.contains(name) {
+                format!("{}::{}", module, name)
+            } else {
+                qualify(name)
+            }
+        };
+
+        let rename_struct = |name: &str| -> String {
+            if struct_names.contains(name) {
+                qualify(&name.to_string())
+            } else {
+                name.to_string()
+            }
+        };
+
+        let rename_types = |types: Vec<Types>| -> Vec<Types> {
+            types.iter().map(|typ| typ.rename_structs(&rename_struct)).collect()
+        };
+
+        let rename_variable = |name: String| -> String {
+            if variable_names.contains(&name) {
+                qualify(&name)
+            } else {
+                name
+            }
+        };
+
+        let rename_operand = |operand: Operand| -> Operand {
+            match operand {
+                Operand::Call(callee) => Operand::Call(rename(&callee)),
+                Operand::PushFunction(callee, inp, outp) => Operand::PushFunction(rename(&callee), rename_types(inp), rename_types(outp)),
+                Operand::NewMap(key, value) => Operand::NewMap(key.rename_structs(&rename_struct), value.rename_structs(&rename_struct)),
+                Operand::StructNew(name, fields) => {
+                    let fields = fields.into_iter().map(|(field, typ)| (field, typ.rename_structs(&rename_struct))).collect();
+                    Operand::StructNew(rename_struct(&name), fields)
+                }
+                Operand::StructGet(name, index, typ) => Operand::StructGet(rename_struct(&name), index, typ.rename_structs(&rename_struct)),
+                Operand::StructSet(name, index, typ) => Operand::StructSet(rename_struct(&name), index, typ.rename_structs(&rename_struct)),
+                Operand::Bind(locals) => {
+                    Operand::Bind(locals.into_iter().map(|(local, typ)| (local, typ.map(|typ| typ.rename_structs(&rename_struct)))).collect())
+                }
+                Operand::LoadVar(name, typ) => Operand::LoadVar(rename_variable(name), typ.rename_structs(&rename_struct)),
+                Operand::StoreVar(name, typ) => Operand::StoreVar(rename_variable(name), typ.rename_structs(&rename_struct)),
+                operand => operand,
+            }
+        };
+
+        for (name, function) in state.operations {
+            let new_name = rename(&name);
+            let origin = state.origins.get(&name).cloned().unwrap_or_else(|| state.file.clone());
+
+            // The same file reached through another include
+            if self.origins.get(&new_name) == Some(&origin) {
+                continue;
+            }
+
+            let operations = function.operations.into_iter().map(|(info, mut op)| {
+                op.data.operand = op.data.operand.map(&rename_operand);
+                (info, op)
+            }).collect();
+
+            let FunctionData(_, inp, outp) = function.data;
+            let (inp, outp) = (rename_types(inp), rename_types(outp));
+
+            if private.contains(&name) {
+                if self.operations.contains_key(&new_name) {
+                    compiler_error(format!("The private function {} included from {} clashes with another included file. Include it with a module name using as", new_name, source), info);
+                }
+
+                self.private.insert(new_name.clone());
+            } else {
+                self.check_included_name(&new_name, &source, info);
+                self.functions.insert(new_name.clone(), (inp.clone(), outp.clone()));
+            }
+
+            self.origins.insert(new_name.clone(), origin);
+            self.operations.insert(new_name.clone(), Function {
+                data: FunctionData(new_name, inp, outp),
+                local_registers: function.local_registers,
+                operations,
+            });
+        }
+
+        for (name, operand) in state.constants {
+            let new_name = qualify(&name);
+            let origin = state.origins.get(&name).cloned().unwrap_or_else(|| state.file.clone());
+
+            if self.origins.get(&new_name) != Some(&origin) {
+                self.check_included_name(&new_name, &source, info);
+                self.origins.insert(new_name.clone(), origin);
+                self.constants.insert(new_name, operand);
+            }
+        }
+
+        for (name, variable) in state.variables {
+            let new_name = qualify(&name);
+            let origin = state.origins.get(&name).cloned().unwrap_or_else(|| state.file.clone());
+
+            if self.origins.get(&new_name) != Some(&origin) {
+                self.check_included_name(&new_name, &source, info);
+                let VariableData(_, typ, initial) = variable;
+                self.origins.insert(new_name.clone(), origin);
+                self.variables.insert(new_name.clone(), VariableData(new_name, typ.rename_structs(&rename_struct), rename_operand(initial)));
+            }
+        }
+
+        for (name, data) in state.structs {
+            let new_name = qualify(&name);
+            let fields = data.1.into_iter().map(|(field, typ)| (field, typ.rename_structs(&rename_struct))).collect::<Vec<_>>();
+
+            match self.structs.get(&new_name) {
+                // The same struct reached through another include
+                Some(existing) if existing.1 == fields => {}
+                Some(_) => {
+                    compiler_error(format!("The struct {} included from {} is already declared. Include it with a module name using as", new_name, source), info);
+                }
+                None => {
+                    self.structs.insert(new_name.clone(), StructData(new_name, fields));
+                }
+            }
+        }
+    }
+
+    /// Public names of an include share one namespace with the functions, constants and variables of the includer
+    fn check_included_name(&self, name: &str, source: &str, info: &OperationDataInfo) {
+        if self.constants.contains_key(name) || self.variables.contains_key(name) || self.functions.contains_key(name) {
+            compiler_error(format!("The name {} included from {} is already declared. Include it with a module name using as", name, source), info);
+        }
+    }
+
+    fn check_global_name(&self, name: &str, info: &OperationDataInfo) {
+        if self.constants.contains_key(name) || self.variables.contains_key(name) || self.functions.contains_key(name) {
+            compiler_error(format!("The name {} is already declared", name), info);
+        }
+    }
+
+    /// Finds the innermost local with the given name.
+    /// Returns its offset from the top of the locals frame
+    fn find_local(&self, name: &str) -> Option<usize> {
+        let locals = self.locals.iter().flat_map(|(_, names)| names).collect::<Vec<_>>();
+        locals.iter().rposition(|local| *local == name).map(|pos| locals.len() - 1 - pos)
+    }
+
+    /// A let without end takes the end of its function, so the function looks unclosed.
+    /// Reports the innermost open let or else the let closed last
+    fn check_unclosed_let(&self, function: &str) {
+        if let Some((info, _)) = self.locals.last() {
+            compiler_error_str("Unclosed let. Expected end", info);
+        }
+
+        if let Some(info) = &self.closed_let {
+            compiler_error(format!("Unclosed function {}. The end of this let may be missing", function), info);
         }
     }
 
@@ -114,7 +602,9 @@ impl State {
             Compile {
                 meta_path: "".to_string(),
                 strip_data: 0,
+                opt_level: 0,
                 readable: false,
+                lib: false,
                 file: "".to_string(),
                 out_file: "".to_string(),
             }
@@ -135,10 +625,6 @@ impl State {
                             if let TokenValue::Keyword(keyword) = value {
                                 match keyword {
                                     Keyword::INCLUDE => {
-                                        if self.incl_lvl > MAX_INCL_DEPTH {
-                                            compiler_error_str("Nested includes are not allowed", &op_data_info);
-                                        }
-
                                         let path = iterator.next();
 
                                         if let None = path {
@@ -147,57 +633,217 @@ impl State {
 
                                         let path = path.unwrap();
 
+                                        let alias = if let Some(TokenValue::Keyword(Keyword::As)) = iterator.clone().next().map(|next| next.1.value()) {
+                                            iterator.next();
+                                            let alias = iterator.next();
+
+                                            if let None = alias {
+                                                compiler_error_str("No module name provided after as. Empty tokenstream", &op_data_info);
+                                            }
+
+                                            let alias = alias.unwrap().1.text().to_string();
+                                            if alias.contains(":") {
+                                                compiler_error(format!("Invalid module name {}", alias), &op_data_info);
+                                            }
+                                            Some(alias)
+                                        } else {
+                                            None
+                                        };
+
                                         if let TokenValue::String(mut s_path) = path.1.value().clone() {
-                                            if s_path.starts_with("@") {
+                                            if s_path.starts_with("@") && !self.includes.resolver.is_package(&s_path) {
                                                 s_path.remove(0);
                                                 if s_path.starts_with("std/") {
+                                                    if alias.is_some() {
+                                                        compiler_error_str("System libraries can not be included with a module name", &op_data_info);
+                                                    }
+
                                                     if !self.sys_libs.contains(&s_path) {
                                                         self.sys_libs.push(s_path);
                                                     }
                                                 } else {
                                                     let pathbuf = PathBuf::from(Path::new(&s_path));
-                                                    let parsed = pre_parse(resolve_opt(&s_path), pathbuf.clone(), pathbuf.clone());
-                                                    let state = tokenize(parsed, self.incl_lvl, pathbuf, Some(comp.clone()));
+                                                    let state = self.include_file(pathbuf.clone(), pathbuf, || resolve_opt(&s_path), &comp, &op_data_info);
 
-                                                    self.functions.extend(state.functions);
-                                                    self.operations.extend(state.operations);
+                                                    self.merge_include(state, alias, &op_data_info);
                                                 }
                                             } else {
-                                                let mut incl_path = self.path.clone();
-                                                incl_path.push(s_path);
+                                                let incl_path = if s_path.starts_with("@") {
+                                                    self.includes.resolver.resolve_package(&s_path)
+                                                } else {
+                                                    self.includes.resolver.resolve(&s_path, &self.path)
+                                                };
 
-                                                let file = OpenOptions::new().read(true).open(&incl_path);
-                                                if file.is_err() {
-                                                    compiler_error(format!("The file {:?} could not be found", incl_path), &op_data_info);
-                                                }
+                                                let incl_path = incl_path.unwrap_or_else(|| {
+                                                    compiler_error(format!("The file {:?} could not be found", s_path), &op_data_info);
+                                                });
 
-                                                let mut string = String::new();
+                                                let state = if incl_path.extension().map_or(false, |extension| extension == LIBRARY_EXTENSION) {
+                                                    self.include_library(incl_path, &op_data_info)
+                                                } else {
+                                                    self.include_file(incl_path.clone(), incl_path.parent().unwrap().to_path_buf(), || {
+                                                        let mut string = String::new();
 
-                                                let mut file = file.unwrap();
-                                                if file.read_to_string(&mut string).is_err() {
-                                                    compiler_error(format!("The file {:?} could not be read from", incl_path), &op_data_info);
-                                                }
+                                                        let file = OpenOptions::new().read(true).open(&incl_path);
+                                                        if file.is_err() || file.unwrap().read_to_string(&mut string).is_err() {
+                                                            compiler_error(format!("The file {:?} could not be read from", incl_path), &op_data_info);
+                                                        }
 
-                                                let parsed = pre_parse(string, incl_path.clone(), incl_path.parent().unwrap().to_path_buf());
-                                                let state = tokenize(parsed, self.incl_lvl, incl_path.parent().unwrap().to_path_buf(), Some(comp.clone()));
+                                                        string
+                                                    }, &comp, &op_data_info)
+                                                };
 
-                                                self.operations.extend(state.operations);
+                                                self.merge_include(state, alias, &op_data_info);
                                             }
                                         } else {
                                             compiler_error(format!("No string passed to include. Found: {:?}", path.1.value()), &op_data_info);
                                             unreachable!()
                                         }
                                     }
+                                    Keyword::Struct => {
+                                        let name = iterator.next();
+
+                                        if let None = name {
+                                            compiler_error_str("No struct name provided. Empty tokenstream", &op_data_info);
+                                        }
+
+                                        let name = name.unwrap().1.text().to_string();
+
+                                        if !name.starts_with(|char: char| char.is_ascii_uppercase()) {
+                                            compiler_error(format!("Struct names have to start with an uppercase letter. Got: {}", name), &op_data_info);
+                                        }
+
+                                        if self.structs.contains_key(&name) {
+                                            compiler_error(format!("The struct {} is already declared", name), &op_data_info);
+                                        }
+
+                                        let mut fields: Vec<(String, Types)> = vec![];
+
+                                        loop {
+                                            let field = iterator.next();
+
+                                            if let None = field {
+                                                compiler_error(format!("Unclosed struct {}", name), &op_data_info);
+                                            }
+
+                                            let field = field.unwrap();
+
+                                            if let TokenValue::Keyword(Keyword::End) = field.1.value() {
+                                                break;
+                                            }
+
+                                            let field_info = OperationDataInfo::from_token(field.1.clone(), &comp);
+
+                                            if let Some((field_name, typ)) = field.1.text().split_once(":") {
+                                                if field_name == "new" || field_name.starts_with("set-") {
+                                                    compiler_error(format!("The field name {} is reserved", field_name), &field_info);
+                                                }
+
+                                                if fields.iter().any(|(existing, _)| existing == field_name) {
+                                                    compiler_error(format!("The field {} is declared twice in struct {}", field_name, name), &field_info);
+                                                }
+
+                                                let typ = Types::from((field.0.clone(), typ.to_string()));
+                                                if typ.contains_descriptor() {
+                                                    compiler_error(format!("The field {} can not hold a descriptor, because field access copies it", field_name), &field_info);
+                                                }
+
+                                                // Structs have to be declared before they are used as a field type
+                                                if let Some(unknown) = typ.structs().into_iter().find(|used| !self.structs.contains_key(used)) {
+                                                    compiler_error(format!("The field {} of struct {} uses the undeclared type {}", field_name, name, unknown), &field_info);
+                                                }
+
+                                                fields.push((field_name.to_string(), typ));
+                                            } else {
+                                                compiler_error(format!("Invalid struct field {}. Fields are declared as name:type", field.1.text()), &field_info);
+                                            }
+                                        }
+
+                                        self.structs.insert(name.clone(), StructData(name, fields));
+                                    }
+                                    Keyword::Local => {
+                                        compiler_error_str("local is only allowed at the start of a function", &op_data_info);
+                                    }
+                                    Keyword::Private => {
+                                        if let Some(TokenValue::Function(name, ..)) = iterator.clone().next().map(|next| next.1.value()) {
+                                            self.private.insert(name.clone());
+                                        } else {
+                                            compiler_error_str("private has to be followed by a function declaration", &op_data_info);
+                                        }
+                                    }
+                                    Keyword::Const => {
+                                        let name = iterator.next();
+                                        let val = iterator.next();
+
+                                        if let None = val {
+                                            compiler_error_str("A constant needs a name and a value. Empty tokenstream", &op_data_info);
+                                        }
+
+                                        let name = name.unwrap().1.text().to_string();
+                                        self.check_global_name(&name, &op_data_info);
+
+                                        let operand = if let Some((operand, _)) = literal(&val.unwrap().1) {
+                                            operand
+                                        } else {
+                                            compiler_error(format!("The constant {} can only be an int, str or bool literal", name), &op_data_info);
+                                        };
+
+                                        self.constants.insert(name, operand);
+                                    }
+                                    Keyword::Var => {
+                                        let decl = iterator.next();
+
+                                        if let None = decl {
+                                            compiler_error_str("No variable declared. Empty tokenstream", &op_data_info);
+                                        }
+
+                                        let decl = decl.unwrap();
+
+                                        if let Some((name, typ)) = decl.1.text().split_once(":") {
+                                            self.check_global_name(name, &op_data_info);
+
+                                            let typ = Types::from((decl.0.clone(), typ.to_string()));
+                                            let default = match RegisterType::default_for(&typ) {
+                                                RegisterType::Int(val) => Operand::Int(val),
+                                                RegisterType::String(val) => Operand::Str(val),
+                                                RegisterType::Bool(val) => Operand::Bool(val),
+                                                RegisterType::Map(key, value, _) => Operand::NewMap(key, value),
+                                                _ => {
+                                                    compiler_error(format!("The variable {} can only be of type int, str, bool or map", name), &op_data_info);
+                                                }
+                                            };
+
+                                            // A literal after the declaration is the initial value
+                                            let initial = iterator.clone().next().and_then(|next| literal(&next.1));
+
+                                            let initial = if let Some((operand, literal_typ)) = initial {
+                                                iterator.next();
+                                                if literal_typ != typ {
+                                                    compiler_error(format!("The initial value of the variable {} is of type {:?}, but the variable is of type {:?}", name, literal_typ, typ), &op_data_info);
+                                                }
+                                                operand
+                                            } else {
+                                                default
+                                            };
+
+                                            self.variables.insert(name.to_string(), VariableData(name.to_string(), typ, initial));
+                                        } else {
+                                            compiler_error(format!("Invalid variable {}. Variables are declared as name:type followed by an optional value", decl.1.text()), &op_data_info);
+                                        }
+                                    }
                                     _ => {
-                                        compiler_error_str("Only includes and functions are allowed on the top level", &op_data_info);
+                                        compiler_error_str("Only includes, structs, constants, variables and functions are allowed on the top level", &op_data_info);
                                     }
                                 }
                             }
                         }
                         TokenType::Function => {
                             if let TokenValue::Function(name, inp, outp) = value {
+                                self.locals.clear();
+                                self.closed_let = None;
                                 self.in_fn = Some(Function {
                                     data: FunctionData(name.clone(), inp.clone(), outp.clone()),
+                                    local_registers: vec![],
                                     operations: vec![],
                                 })
                             } else {
@@ -205,7 +851,7 @@ impl State {
                             }
                         }
                         _ => {
-                            compiler_error_str("Only includes and functions are allowed on the top level", &op_data_info);
+                            compiler_error_str("Only includes, structs, constants, variables and functions are allowed on the top level", &op_data_info);
                         }
                     }
                 } else {
@@ -238,6 +884,29 @@ impl State {
                                     compiler_error_str("Invalid ressource operation.", &op_data_info);
                                     unreachable!()
                                 }
+                            } else if text.starts_with("map-new<") && text.ends_with(">") {
+                                if !sys_libs.contains(&"std/maps".to_string()) {
+                                    compiler_error_str("map-new is only available after including \"@std/maps\"", &op_data_info);
+                                }
+
+                                let mut typ = text.clone();
+                                typ.replace_range(0.."map-new".len(), "map");
+
+                                if let Types::Map(key, value) = Types::from((token.location().clone(), typ)) {
+                                    if value.contains_descriptor() {
+                                        compiler_error_str("Maps can not hold descriptors, because map-get copies them", &op_data_info);
+                                    }
+
+                                    vec![
+                                        Operation::new(
+                                            OperationData::new(OperationType::Push, token, &comp, Some(Operand::NewMap(*key, *value))),
+                                            SIMPLE_RUNTIME.clone(),
+                                            SIMPLE_TYPECHECK.clone(),
+                                        )
+                                    ]
+                                } else {
+                                    compiler_error_str("Internal parser error occurred", &op_data_info);
+                                }
                             } else if text.starts_with("~") && {
                                 let mut token = text.clone();
                                 token.remove(0);
@@ -256,12 +925,97 @@ impl State {
                                         SIMPLE_TYPECHECK.clone(),
                                     )
                                 ]
+                            } else if let Some(offset) = self.find_local(&text) {
+                                vec![
+                                    Operation::new(
+                                        OperationData::new(OperationType::Local, token, &comp, Some(Operand::PushLocal(text, offset))),
+                                        LOCAL_RUNTIME.clone(),
+                                        LOCAL_TYPECHECK.clone(),
+                                    )
+                                ]
+                            } else if let Some(operand) = self.constants.get(&text) {
+                                vec![
+                                    Operation::new(
+                                        OperationData::new(OperationType::Push, token, &comp, Some(operand.clone())),
+                                        SIMPLE_RUNTIME.clone(),
+                                        SIMPLE_TYPECHECK.clone(),
+                                    )
+                                ]
+                            } else if let Some(variable) = text.strip_suffix("@").or(text.strip_suffix("!")).and_then(|name| self.variables.get(name)) {
+                                let name = variable.0.clone();
+                                let typ = variable.typ().clone();
+                                let operand = if text.ends_with("@") {
+                                    Operand::LoadVar(name, typ)
+                                } else {
+                                    Operand::StoreVar(name, typ)
+                                };
+
+                                vec![
+                                    Operation::new(
+                                        OperationData::new(OperationType::Global, token, &comp, Some(operand)),
+                                        GLOBAL_RUNTIME.clone(),
+                                        GLOBAL_TYPECHECK.clone(),
+                                    )
+                                ]
+                            } else if let Some(struct_data) = text.split_once(".").and_then(|(name, _)| self.structs.get(name)) {
+                                let (_, member) = text.split_once(".").unwrap();
+                                let name = struct_data.name();
+                                let fields = struct_data.fields();
+
+                                let operand = if member == "new" {
+                                    Operand::StructNew(name, fields.clone())
+                                } else {
+                                    let (field, set) = member.strip_prefix("set-").map_or((member, false), |field| (field, true));
+                                    let index = fields.iter().position(|(existing, _)| existing == field);
+
+                                    if let None = index {
+                                        compiler_error(format!("The struct {} has no field {}", name, field), &op_data_info);
+                                    }
+
+                                    let index = index.unwrap();
+                                    let typ = fields.get(index).unwrap().1.clone();
+
+                                    if set {
+                                        Operand::StructSet(name, index, typ)
+                                    } else {
+                                        Operand::StructGet(name, index, typ)
+                                    }
+                                };
+
+                                vec![
+                                    Operation::new(
+                                        OperationData::new(OperationType::Struct, token, &comp, Some(operand)),
+                                        STRUCT_RUNTIME.clone(),
+                                        STRUCT_TYPECHECK.clone(),
+                                    )
+                                ]
                             } else if self.functions.contains_key(&text) {
                                 vec![Operation::new(OperationData::new(OperationType::Call, token, &comp, Some(Operand::Call(text))),
                                                     CALLING_RUNTIME.clone(),
                                                     CALLING_TYPECHECK.clone(), )]
                             } else {
-                                let internal = to_internal(sys_libs, value, &op_data_info);
+                                let internal = match to_internal(sys_libs, value, &op_data_info) {
+                                    Internal::Pick(_) | Internal::Roll(_) => {
+                                        let depth = iterator.next();
+
+                                        let depth = if let Some(TokenValue::Int(depth)) = depth.map(|depth| depth.1.value()) {
+                                            u8::try_from(*depth).ok()
+                                        } else {
+                                            None
+                                        };
+
+                                        if let None = depth {
+                                            compiler_error(format!("{} has to be followed by a positive int literal", text), &op_data_info);
+                                        }
+
+                                        if text == "pick" {
+                                            Internal::Pick(depth.unwrap())
+                                        } else {
+                                            Internal::Roll(depth.unwrap())
+                                        }
+                                    }
+                                    internal => internal,
+                                };
                                 vec![Operation::new(
                                     OperationData::new(OperationType::Internal, token, &comp, Some(Operand::Internal(internal))),
                                     INTERNAL_RUNTIME.clone(),
@@ -302,6 +1056,32 @@ impl State {
                                     Keyword::INCLUDE => {
                                         compiler_error_str("Include is only allowed on the top level", &op_data_info);
                                     }
+                                    Keyword::Struct => {
+                                        compiler_error_str("Structs are only allowed on the top level", &op_data_info);
+                                    }
+                                    Keyword::Const | Keyword::Var => {
+                                        compiler_error_str("Constants and variables are only allowed on the top level", &op_data_info);
+                                    }
+                                    Keyword::As | Keyword::Private => {
+                                        compiler_error_str("Module keywords are only allowed on the top level", &op_data_info);
+                                    }
+                                    Keyword::Local => {
+                                        if !function.operations.is_empty() || !function.local_registers.is_empty() {
+                                            compiler_error_str("local is only allowed once at the start of a function", &op_data_info);
+                                        }
+
+                                        // Every following register name is declared local
+                                        while let Some(register) = iterator.clone().next().and_then(|next| register_index(next.1.text())) {
+                                            iterator.next();
+                                            if !function.local_registers.contains(&register) {
+                                                function.local_registers.push(register);
+                                            }
+                                        }
+
+                                        if function.local_registers.is_empty() {
+                                            compiler_error_str("local has to be followed by the registers a to h", &op_data_info);
+                                        }
+                                    }
                                     Keyword::Call | Keyword::CallIf => {
                                         ops.push(Operation::new(OperationData::new(
                                             if keyword.clone() == Keyword::Call {
@@ -313,10 +1093,68 @@ impl State {
                                                                 CALLING_TYPECHECK.clone(),
                                         ))
                                     }
+                                    Keyword::Let => {
+                                        let mut tokens = vec![];
+
+                                        loop {
+                                            // Anything but a name ends the let, so a missing in is reported at the let
+                                            match iterator.next() {
+                                                Some(local) if local.1.typ() == &TokenType::Word => tokens.push(local),
+                                                Some(local) if matches!(local.1.value(), TokenValue::Keyword(Keyword::In)) => break,
+                                                _ => compiler_error_str("Unclosed let. Expected in", &op_data_info),
+                                            }
+                                        }
+
+                                        let mut locals: Vec<(String, Option<Types>)> = vec![];
+
+                                        for local in tokens {
+                                            let local_info = OperationDataInfo::from_token(local.1.clone(), &comp);
+
+                                            // The type can be omitted. It is then taken from the stack by the type check
+                                            let (name, typ) = match local.1.text().split_once(":") {
+                                                Some((name, typ)) => (name, Some(Types::from((local.0.clone(), typ.to_string())))),
+                                                None => (local.1.text(), None),
+                                            };
+
+                                            if name.is_empty() {
+                                                compiler_error(format!("Invalid local {}. Locals are declared as name or name:type", local.1.text()), &local_info);
+                                            }
+
+                                            if locals.iter().any(|(existing, _)| existing == name) {
+                                                compiler_error(format!("The local {} is bound twice", name), &local_info);
+                                            }
+
+                                            if typ.as_ref().map_or(false, |typ| typ.contains_descriptor()) {
+                                                compiler_error(format!("The local {} can not hold a descriptor, because every use copies it", name), &local_info);
+                                            }
+
+                                            locals.push((name.to_string(), typ));
+                                        }
+
+                                        self.locals.push((op_data_info.clone(), locals.iter().map(|(name, _)| name.clone()).collect()));
+
+                                        ops.push(Operation::new(
+                                            OperationData::new(OperationType::Local, token, &comp, Some(Operand::Bind(locals))),
+                                            LOCAL_RUNTIME.clone(),
+                                            LOCAL_TYPECHECK.clone(),
+                                        ))
+                                    }
+                                    Keyword::In => {
+                                        compiler_error_str("in is only allowed after let", &op_data_info);
+                                    }
                                     Keyword::End => {
-                                        self.operations.insert(function.data.clone().0, function);
-                                        self.in_fn = None;
-                                        continue;
+                                        if let Some((info, locals)) = self.locals.pop() {
+                                            self.closed_let = Some(info);
+                                            ops.push(Operation::new(
+                                                OperationData::new(OperationType::Local, token, &comp, Some(Operand::Unbind(locals.len()))),
+                                                LOCAL_RUNTIME.clone(),
+                                                LOCAL_TYPECHECK.clone(),
+                                            ))
+                                        } else {
+                                            self.operations.insert(function.data.clone().0, function);
+                                            self.in_fn = None;
+                                            continue;
+                                        }
                                     }
                                 }
                             }
@@ -324,6 +1162,7 @@ impl State {
                             ops
                         }
                         TokenType::Function => {
+                            self.check_unclosed_let(&function.data.0);
                             compiler_error_str("Functions are only allowed on the top level", &op_data_info);
                             unreachable!()
                         }
@@ -358,6 +1197,7 @@ impl State {
         }
 
         if let Some(ref fnc) = self.in_fn {
+            self.check_unclosed_let(&fnc.data.0);
             compiler_error(format!("Unclosed function {}", fnc.data.0), &OperationDataInfo::None);
         }
 
@@ -365,6 +1205,7 @@ impl State {
         self.operations = self.operations.clone().iter().map(|entry: (&String, &Function)| {
             (entry.clone().0.clone(), Function {
                 data: entry.1.data.clone(),
+                local_registers: entry.1.local_registers.clone(),
                 operations: entry.1.operations.iter().map(|op| {
                     if op.1.data.typ == OperationType::PushFunction {
                         let mut operation = op.1.clone();
@@ -386,44 +1227,64 @@ impl State {
 
     pub fn type_check(self) -> Result<VM, String> {
         for (name, function) in &self.operations {
-            let mut stack = function.get_contract().0;
+            let (inp, outp) = function.get_contract();
+            let unknown = inp.iter().chain(outp.iter())
+                .flat_map(|typ| typ.structs())
+                .find(|typ| !self.structs.contains_key(typ));
 
-            let type_check = function.type_check(self.operations.clone(), &mut stack);
+            if let Some(unknown) = unknown {
+                return Err(format!("Function {} uses the undeclared type {}", name, unknown));
+            }
 
-            if type_check.error != ErrorTypes::None {
-                return Err(format!("Function {} failed type check: {}", name, type_check));
+            // Types written inside the body, like let x:Point or map-new<str,Point>, are checked the same way
+            for (info, op) in &function.operations {
+                let types = match &op.data.operand {
+                    Some(Operand::Bind(names)) => names.iter().filter_map(|(_, typ)| typ.clone()).collect(),
+                    Some(Operand::NewMap(key, value)) => vec![key.clone(), value.clone()],
+                    Some(Operand::PushFunction(_, inp, outp)) => inp.iter().chain(outp.iter()).cloned().collect(),
+                    _ => vec![],
+                };
+
+                if let Some(unknown) = types.iter().flat_map(|typ| typ.structs()).find(|typ| !self.structs.contains_key(typ)) {
+                    return Err(format!("Function {} uses the undeclared type {} {}", name, unknown, info));
+                }
             }
+        }
 
-            if stack != function.get_contract().1 {
-                return Err(format!("Function {} failed type check! You still have unused elements left. Elements are: {:?}", name, stack));
+        for (name, variable) in &self.variables {
+            if let Some(unknown) = variable.typ().structs().into_iter().find(|typ| !self.structs.contains_key(typ)) {
+                return Err(format!("The variable {} uses the undeclared type {}", name, unknown));
             }
         }
 
+        // Main starts with empty registers. The functions it calls are checked with the registers at the call
+        let mut context = RegisterContext::new(&self.operations);
+        if self.operations.contains_key("main") {
+            context.check("main", vec![None; REGISTER_COUNT])?;
+        }
+
+        // Functions which are not called from main, like the ones only called through pointers, start with empty registers
+        for name in self.operations.keys() {
+            if !context.checked.contains_key(name) {
+                context.check(name, vec![None; REGISTER_COUNT])?;
+            }
+        }
 
         Ok(VM::from(self))
     }
 
-    pub fn compile(self, meta: ProgramMetadata, readable: bool) -> Result<Vec<u8>, String> {
+    pub fn compile(self, meta: ProgramMetadata, readable: bool, opt_level: u8) -> Result<Vec<u8>, String> {
+        let structs = self.structs.clone();
+        let variables = self.variables.clone();
         let vm = self.type_check()?;
-        let fncs = vm.ops().iter().map(|entry| {
-            let ops = entry.1.operations.iter().map(|op| {
-                let nop = op.1.data.clone();
-                (op.0.clone(), nop)
-            }).collect::<Vec<_>>();
-
-            let fn_data = entry.1.data.clone();
-
-            let fnc = CompiledFunction {
-                data: fn_data,
-                operations: ops,
-            };
-
-            (entry.0.clone(), fnc)
-        }).collect::<HashMap<_, _>>();
+        let ops = optimize(vm.ops().clone(), opt_level, vec!["main".to_string()]);
+        let fncs = ops.iter().map(|entry| (entry.0.clone(), CompiledFunction::from(entry.1))).collect::<HashMap<_, _>>();
 
         let program = CompiledProgram {
             data: meta,
             operations: fncs,
+            structs,
+            variables,
         };
 
         let res = if readable {
@@ -435,9 +1296,43 @@ impl State {
         Ok(res)
     }
 
+    /// Compiles into a library exporting every function which is not private
+    pub fn compile_library(self, meta: ProgramMetadata, readable: bool, opt_level: u8) -> Result<Vec<u8>, String> {
+        let structs = self.structs.clone();
+        let variables = self.variables.clone();
+        let exports = self.functions.iter()
+            .filter(|(name, _)| !self.private.contains(*name))
+            .map(|(name, contract)| (name.clone(), contract.clone()))
+            .collect::<HashMap<_, _>>();
+
+        let vm = self.type_check()?;
+        let ops = optimize(vm.ops().clone(), opt_level, exports.keys().cloned().collect());
+        let fncs = ops.iter().map(|entry| (entry.0.clone(), CompiledFunction::from(entry.1))).collect::<HashMap<_, _>>();
+
+        let library = CompiledLibrary {
+            data: meta,
+            exports,
+            operations: fncs,
+            structs,
+            variables,
+        };
+
+        let res = if readable {
+            serde_yaml::to_vec(&library).unwrap()
+        } else {
+            bincode::serialize(&library).unwrap()
+        };
+
+        Ok(res)
+    }
+
     pub fn get_ops(&self) -> &HashMap<String, Function> {
         &self.operations
     }
+
+    pub fn get_variables(&self) -> &HashMap<String, VariableData> {
+        &self.variables
+    }
 }
 
 pub fn pre_parse(string: String, file: PathBuf, path: PathBuf) -> Vec<(Position, String)> {
@@ -535,8 +1430,12 @@ pub fn pre_parse(string: String, file: PathBuf, path: PathBuf) -> Vec<(Position,
     lines
 }
 
-pub fn tokenize(tokens: Vec<(Position, String)>, included: u8, path: PathBuf, comp: Option<Compile>) -> State {
-    let mut state = if included != 0 { State::new_with_include(included, path) } else { State::new(path) };
+pub fn tokenize(tokens: Vec<(Position, String)>, file: PathBuf, path: PathBuf, comp: Option<Compile>, resolver: IncludeResolver, cache: Option<ParseCache>) -> State {
+    let file = fs::canonicalize(&file).unwrap_or(file);
+    let mut state = State::new(file.clone(), path);
+    state.includes.chain.push(file);
+    state.includes.resolver = resolver;
+    state.includes.cache = cache;
 
     state.update(tokens.par_iter().map(|token| {
         let token = token.clone();
@@ -0,0 +1,134 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::{StructData, VariableData};
+use crate::util::compile::CompiledFunction;
+use crate::util::operation::Operand;
+use crate::util::project::IncludeResolver;
+use crate::util::type_check::Types;
+
+pub fn hash_file(path: &Path) -> Option<u64> {
+    let content = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// The version of the compiler. Development builds share the package version, so the build time of the executable is part of it
+pub(crate) fn compiler_version() -> String {
+    let built = std::env::current_exe().ok()
+        .and_then(|exe| fs::metadata(exe).ok())
+        .and_then(|metadata| metadata.modified().ok())
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |built| built.as_secs());
+
+    format!("{}+{}", env!("CARGO_PKG_VERSION"), built)
+}
+
+/// The parse result of a single file including everything merged from its includes
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CachedState {
+    pub(crate) operations: HashMap<String, CompiledFunction>,
+    pub(crate) functions: HashMap<String, (Vec<Types>, Vec<Types>)>,
+    pub(crate) structs: HashMap<String, StructData>,
+    pub(crate) constants: HashMap<String, Operand>,
+    pub(crate) variables: HashMap<String, VariableData>,
+    pub(crate) sys_libs: Vec<String>,
+    pub(crate) private: HashSet<String>,
+    pub(crate) origins: HashMap<String, PathBuf>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CachedFile {
+    version: String,
+    strip_data: usize,
+    resolver: u64,
+    hash: u64,
+    /// Every file reached through the includes with its hash when it was parsed
+    includes: Vec<(PathBuf, u64)>,
+    pub(crate) edges: HashMap<PathBuf, Vec<PathBuf>>,
+    pub(crate) state: CachedState,
+}
+
+/// Stores parsed files in a folder.
+/// An entry is only used while the compiler version, the settings, the include folders,
+/// the file and all files it includes are unchanged
+#[derive(Clone)]
+pub struct ParseCache {
+    dir: PathBuf,
+    version: String,
+    resolver: u64,
+}
+
+impl ParseCache {
+    pub fn new(dir: PathBuf, resolver: &IncludeResolver) -> Self {
+        Self {
+            dir,
+            version: compiler_version(),
+            resolver: resolver.fingerprint(),
+        }
+    }
+
+    fn entry_path(&self, file: &Path, strip_data: usize) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        file.hash(&mut hasher);
+        self.version.hash(&mut hasher);
+        strip_data.hash(&mut hasher);
+        self.resolver.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.cache", hasher.finish()))
+    }
+
+    pub fn load(&self, file: &Path, strip_data: usize) -> Option<CachedFile> {
+        let content = fs::read(self.entry_path(file, strip_data)).ok()?;
+        let cached = bincode::deserialize::<CachedFile>(&content).ok()?;
+
+        let valid = cached.version == self.version
+            && cached.strip_data == strip_data
+            && cached.resolver == self.resolver
+            && Some(cached.hash) == hash_file(file)
+            && cached.includes.iter().all(|(include, hash)| Some(*hash) == hash_file(include));
+
+        if valid {
+            Some(cached)
+        } else {
+            None
+        }
+    }
+
+    /// Stores the state of a file. Failing to write the cache is not an error
+    pub fn store(&self, file: &Path, strip_data: usize, edges: HashMap<PathBuf, Vec<PathBuf>>, state: CachedState) {
+        let hash = hash_file(file);
+        if let None = hash {
+            return;
+        }
+
+        let includes = edges.values()
+            .flatten()
+            .filter(|include| include.is_file())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|include| (include.clone(), hash_file(include).unwrap_or_default()))
+            .collect();
+
+        let cached = CachedFile {
+            version: self.version.clone(),
+            strip_data,
+            resolver: self.resolver,
+            hash: hash.unwrap(),
+            includes,
+            edges,
+            state,
+        };
+
+        if fs::create_dir_all(&self.dir).is_ok() {
+            if let Ok(content) = bincode::serialize(&cached) {
+                let _ = fs::write(self.entry_path(file, strip_data), content);
+            }
+        }
+    }
+}
@@ -4,25 +4,63 @@ use serde::{Deserialize, Serialize};
 
 use crate::{OperationDataInfo, VM};
 use crate::args::Compile;
-use crate::parser::{Function, FunctionData};
+use crate::parser::{Function, FunctionData, StructData, VariableData};
 use crate::util::operation::{Operation, OperationData};
+use crate::util::type_check::Types;
+
+/// The file extension of compiled libraries
+pub const LIBRARY_EXTENSION: &'static str = "scylib";
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct CompiledFunction {
     pub(crate) data: FunctionData,
+    pub(crate) local_registers: Vec<usize>,
     pub(crate) operations: Vec<(OperationDataInfo, OperationData)>,
 }
 
+impl From<&Function> for CompiledFunction {
+    fn from(function: &Function) -> Self {
+        Self {
+            data: function.data.clone(),
+            local_registers: function.local_registers.clone(),
+            operations: function.operations.iter().map(|op| (op.0.clone(), op.1.data.clone())).collect(),
+        }
+    }
+}
+
+impl From<CompiledFunction> for Function {
+    fn from(function: CompiledFunction) -> Self {
+        Self {
+            data: function.data,
+            local_registers: function.local_registers,
+            operations: function.operations.into_iter().map(|op| (op.0, Operation::from(op.1))).collect(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct ProgramMetadata {
-    pub(crate) name: String,
-    pub(crate) version: String,
-    pub(crate) author: Option<String>,
-    pub(crate) description: Option<String>,
+    pub name: String,
+    pub version: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct CompiledProgram {
+    pub data: ProgramMetadata,
+    pub(crate) operations: HashMap<String, CompiledFunction>,
+    pub(crate) structs: HashMap<String, StructData>,
+    pub(crate) variables: HashMap<String, VariableData>,
+}
+
+/// A separately compiled library.
+/// Programs including it can only call the exported functions
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CompiledLibrary {
     pub(crate) data: ProgramMetadata,
+    pub(crate) exports: HashMap<String, (Vec<Types>, Vec<Types>)>,
     pub(crate) operations: HashMap<String, CompiledFunction>,
-}
\ No newline at end of file
+    pub(crate) structs: HashMap<String, StructData>,
+    pub(crate) variables: HashMap<String, VariableData>,
+}
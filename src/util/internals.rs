@@ -38,8 +38,18 @@ static STACK_OPS_INTERNALS_MAP: SyncLazy<HashMap<&'static str, Internal>> = Sync
     let mut map = HashMap::new();
     map.insert("swap", Internal::Swap);
     map.insert("drop", Internal::Drop);
-    map.insert("swap", Internal::Swap);
     map.insert("dup", Internal::Dup);
+    map.insert("over", Internal::Over);
+    map.insert("rot", Internal::Rot);
+    map.insert("-rot", Internal::RotBack);
+    map.insert("nip", Internal::Nip);
+    map.insert("tuck", Internal::Tuck);
+    map.insert("2dup", Internal::TwoDup);
+    map.insert("2drop", Internal::TwoDrop);
+    map.insert("2swap", Internal::TwoSwap);
+    map.insert("pick", Internal::Pick(0));
+    map.insert("roll", Internal::Roll(0));
+    map.insert("depth", Internal::Depth);
     map.insert("rev-stack", Internal::RevStack);
     map.insert("drop-stack", Internal::DropStack);
     map.insert("dup-stack", Internal::DupStack);
@@ -71,12 +81,80 @@ static BOOL_INTERNALS_MAP: SyncLazy<HashMap<&'static str, Internal>> = SyncLazy:
     map
 });
 
+static MAPS_INTERNALS_MAP: SyncLazy<HashMap<&'static str, Internal>> = SyncLazy::new(|| {
+    let mut map = HashMap::new();
+    map.insert("map-set", Internal::MapSet);
+    map.insert("map-get", Internal::MapGet);
+    map.insert("map-has", Internal::MapHas);
+    map.insert("map-remove", Internal::MapRemove);
+    map.insert("map-keys", Internal::MapKeys);
+    map.insert("map-size", Internal::MapSize);
+    map.insert("map-for-each", Internal::MapForEach);
+    map
+});
+
+static FS_INTERNALS_MAP: SyncLazy<HashMap<&'static str, Internal>> = SyncLazy::new(|| {
+    let mut map = HashMap::new();
+    map.insert("fs-exists", Internal::FsExists);
+    map.insert("fs-is-dir", Internal::FsIsDir);
+    map.insert("fs-mkdir", Internal::FsMkdir);
+    map.insert("fs-remove", Internal::FsRemove);
+    map.insert("fs-rename", Internal::FsRename);
+    map.insert("fs-copy", Internal::FsCopy);
+    map.insert("fs-list-dir", Internal::FsListDir);
+    map.insert("fs-size", Internal::FsSize);
+    map.insert("fs-mtime", Internal::FsMtime);
+    map
+});
+
+static ENV_INTERNALS_MAP: SyncLazy<HashMap<&'static str, Internal>> = SyncLazy::new(|| {
+    let mut map = HashMap::new();
+    map.insert("argc", Internal::EnvArgc);
+    map.insert("arg", Internal::EnvArg);
+    map.insert("env-get", Internal::EnvGet);
+    map.insert("env-set", Internal::EnvSet);
+    map
+});
+
+static REGISTERS_INTERNALS_MAP: SyncLazy<HashMap<&'static str, Internal>> = SyncLazy::new(|| {
+    let mut map = HashMap::new();
+    map.insert(">a", Internal::RegisterStore(0));
+    map.insert("a>", Internal::RegisterLoad(0));
+    map.insert("a@", Internal::RegisterPeek(0));
+    map.insert(">b", Internal::RegisterStore(1));
+    map.insert("b>", Internal::RegisterLoad(1));
+    map.insert("b@", Internal::RegisterPeek(1));
+    map.insert(">c", Internal::RegisterStore(2));
+    map.insert("c>", Internal::RegisterLoad(2));
+    map.insert("c@", Internal::RegisterPeek(2));
+    map.insert(">d", Internal::RegisterStore(3));
+    map.insert("d>", Internal::RegisterLoad(3));
+    map.insert("d@", Internal::RegisterPeek(3));
+    map.insert(">e", Internal::RegisterStore(4));
+    map.insert("e>", Internal::RegisterLoad(4));
+    map.insert("e@", Internal::RegisterPeek(4));
+    map.insert(">f", Internal::RegisterStore(5));
+    map.insert("f>", Internal::RegisterLoad(5));
+    map.insert("f@", Internal::RegisterPeek(5));
+    map.insert(">g", Internal::RegisterStore(6));
+    map.insert("g>", Internal::RegisterLoad(6));
+    map.insert("g@", Internal::RegisterPeek(6));
+    map.insert(">h", Internal::RegisterStore(7));
+    map.insert("h>", Internal::RegisterLoad(7));
+    map.insert("h@", Internal::RegisterPeek(7));
+    map
+});
+
 static INCLUDE_MAP: SyncLazy<HashMap<&'static str, &'static HashMap<&'static str, Internal>>> = SyncLazy::new(|| {
     let mut map = HashMap::new();
     map.insert("std/bool", &*BOOL_INTERNALS_MAP);
     map.insert("std/simple-maths", &*BASIC_MATH_INTERNALS_MAP);
     map.insert("std/stack-ops", &*STACK_OPS_INTERNALS_MAP);
     map.insert("std/reflection", &*REFLECTION_INTERNALS_MAP);
+    map.insert("std/maps", &*MAPS_INTERNALS_MAP);
+    map.insert("std/registers", &*REGISTERS_INTERNALS_MAP);
+    map.insert("std/fs", &*FS_INTERNALS_MAP);
+    map.insert("std/env", &*ENV_INTERNALS_MAP);
     map
 });
 
@@ -90,6 +168,19 @@ pub enum Internal {
     Swap,
     Drop,
     Dup,
+    Over,
+    Rot,
+    RotBack,
+    Nip,
+    Tuck,
+    TwoDup,
+    TwoDrop,
+    TwoSwap,
+    /// Copies the n-th element below the top. The depth is fixed at compile time
+    Pick(u8),
+    /// Moves the n-th element below the top to the top. The depth is fixed at compile time
+    Roll(u8),
+    Depth,
     RevStack,
     DropStack,
     DupStack,
@@ -112,6 +203,49 @@ pub enum Internal {
     ReflectionRemoveStrDrop,
     ReflectionPush,
     ReflectionClear,
+    MapSet,
+    MapGet,
+    MapHas,
+    MapRemove,
+    MapKeys,
+    MapSize,
+    MapForEach,
+    FsExists,
+    FsIsDir,
+    /// Creates the folder and all missing parents
+    FsMkdir,
+    /// Removes a file or a folder with its content
+    FsRemove,
+    FsRename,
+    FsCopy,
+    /// Lists the names in a folder as a map from index to name
+    FsListDir,
+    /// The size of a file in bytes. Fails for files of 2 GiB or more, because they do not fit into an int
+    FsSize,
+    /// The last modification as seconds since the unix epoch. Fails for times after 2038, because they do not fit into an int
+    FsMtime,
+    /// The number of arguments passed to the program after --
+    EnvArgc,
+    /// The n-th argument and whether it exists
+    EnvArg,
+    /// The value of an environment variable and whether it is set
+    EnvGet,
+    EnvSet,
+    /// Pops the top of the stack into the register
+    RegisterStore(u8),
+    /// Moves the register content onto the stack
+    RegisterLoad(u8),
+    /// Copies the register content onto the stack
+    RegisterPeek(u8),
+}
+
+impl Internal {
+    pub fn register(&self) -> Option<usize> {
+        match self {
+            Internal::RegisterStore(index) | Internal::RegisterLoad(index) | Internal::RegisterPeek(index) => Some(*index as usize),
+            _ => None,
+        }
+    }
 }
 
 pub fn to_internal(includes: Vec<String>, str: &TokenValue, pos: &OperationDataInfo) -> Internal {
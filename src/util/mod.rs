@@ -9,6 +9,9 @@ pub mod register_type;
 pub mod type_check;
 pub mod operations;
 pub mod compile;
+pub mod cache;
+pub mod optimize;
+pub mod project;
 
 pub fn compiler_error(msg: String, pos: &OperationDataInfo) -> ! {
     panic!("ERROR {} -> {}", pos, msg);
@@ -35,7 +38,7 @@ pub fn runtime_error_str(msg: &str, pos: &OperationDataInfo) -> ! {
 }
 
 pub fn runtime_warning(msg: String, pos: &OperationDataInfo) {
-    eprintln!("RUNTIME ERROR {} -> {}", pos, msg);
+    eprintln!("RUNTIME WARNING {} -> {}", pos, msg);
 }
 
 pub fn runtime_warning_str(msg: &str, pos: &OperationDataInfo) {
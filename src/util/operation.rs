@@ -9,9 +9,10 @@ use crate::args::Compile;
 use crate::parser::Function;
 use crate::util::compiler_warning_str;
 use crate::util::internals::Internal;
-use crate::util::operations::{CALLING_RUNTIME, CALLING_TYPECHECK, DESCRIPTOR_RUNTIME, DESCRIPTOR_TYPECHECK, DescriptorAction, INTERNAL_RUNTIME, INTERNAL_TYPECHECK, SIMPLE_RUNTIME, SIMPLE_TYPECHECK};
+use crate::util::operations::{local_typecheck, register_typecheck, CALLING_RUNTIME, CALLING_TYPECHECK, DESCRIPTOR_RUNTIME, DESCRIPTOR_TYPECHECK, GLOBAL_RUNTIME, GLOBAL_TYPECHECK, INTERNAL_RUNTIME, INTERNAL_TYPECHECK, LOCAL_RUNTIME, LOCAL_TYPECHECK, SIMPLE_RUNTIME, SIMPLE_TYPECHECK, STRUCT_RUNTIME, STRUCT_TYPECHECK};
 use crate::util::token::Token;
 use crate::util::type_check::{TypeCheckError, Types};
+use crate::vm::REGISTER_COUNT;
 
 #[derive(Serialize, Deserialize, Ord, PartialOrd, Eq, PartialEq, Clone, Debug)]
 pub enum OperationType {
@@ -23,6 +24,9 @@ pub enum OperationType {
     JumpIf,
     Call,
     CallIf,
+    Struct,
+    Local,
+    Global,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -34,6 +38,15 @@ pub enum Operand {
     PushFunction(String, Vec<Types>, Vec<Types>),
     Call(String),
     DescriptorAction(String, String),
+    NewMap(Types, Types),
+    StructNew(String, Vec<(String, Types)>),
+    StructGet(String, usize, Types),
+    StructSet(String, usize, Types),
+    Bind(Vec<(String, Option<Types>)>),
+    PushLocal(String, usize),
+    Unbind(usize),
+    LoadVar(String, Types),
+    StoreVar(String, Types),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -50,6 +63,21 @@ pub struct OperationData {
     pub(crate) operand: Option<Operand>,
 }
 
+/// The types tracked next to the type stack while a function is type checked
+#[derive(Clone, Debug)]
+pub struct TypeFrame {
+    /// The types stored in the registers a to h. None if the register is empty
+    pub registers: Vec<Option<Types>>,
+    /// The types of the bound locals. The innermost local is last
+    pub locals: Vec<Types>,
+}
+
+impl TypeFrame {
+    pub fn new() -> Self {
+        TypeFrame { registers: vec![None; REGISTER_COUNT], locals: vec![] }
+    }
+}
+
 #[derive(Clone)]
 pub struct Operation {
     pub(crate) data: OperationData,
@@ -68,6 +96,21 @@ impl Operation {
         self.type_check.call((&self.data, functions, stack, compile_time))
     }
 
+    /// Type checks the operation while tracking the types stored in the registers and locals
+    pub fn type_check_with_frame(&self, functions: &HashMap<String, Function>, stack: &mut Vec<Types>, frame: &mut TypeFrame, compile_time: bool) -> TypeCheckError {
+        if let Some(Operand::Internal(internal)) = &self.data.operand {
+            if internal.register().is_some() {
+                return register_typecheck(internal, stack, &mut frame.registers);
+            }
+        }
+
+        if self.data.typ == OperationType::Local {
+            return local_typecheck(self.data.operand.as_ref(), stack, &mut frame.locals);
+        }
+
+        self.type_check(functions, stack, compile_time)
+    }
+
     pub fn execute_op(&self, vm: &mut VM) {
         self.execute_fn.call((&self.data, vm));
     }
@@ -139,7 +182,8 @@ impl From<OperationData> for Operation {
                 Operation::new(data, SIMPLE_RUNTIME.clone(), SIMPLE_TYPECHECK.clone())
             }
             OperationType::PushFunction => {
-                Operation::new(data, CALLING_RUNTIME.clone(), CALLING_TYPECHECK.clone())
+                // ~name only pushes the function. The parser attaches the same closures
+                Operation::new(data, SIMPLE_RUNTIME.clone(), SIMPLE_TYPECHECK.clone())
             }
             OperationType::Internal => {
                 Operation::new(data, INTERNAL_RUNTIME.clone(), INTERNAL_TYPECHECK.clone())
@@ -153,6 +197,15 @@ impl From<OperationData> for Operation {
             OperationType::CallIf => {
                 Operation::new(data, CALLING_RUNTIME.clone(), CALLING_TYPECHECK.clone())
             }
+            OperationType::Struct => {
+                Operation::new(data, STRUCT_RUNTIME.clone(), STRUCT_TYPECHECK.clone())
+            }
+            OperationType::Local => {
+                Operation::new(data, LOCAL_RUNTIME.clone(), LOCAL_TYPECHECK.clone())
+            }
+            OperationType::Global => {
+                Operation::new(data, GLOBAL_RUNTIME.clone(), GLOBAL_TYPECHECK.clone())
+            }
             _ => {
                 unreachable!()
             }
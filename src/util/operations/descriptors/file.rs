@@ -1,96 +1,139 @@
 use std::any::Any;
 use std::fs::{File as StdFile, OpenOptions};
-use std::io::{BufRead, BufReader, BufWriter, Read, Write};
-use std::path::Path;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
 
-use crate::Position;
 use crate::util::operation::OperationDataInfo;
-use crate::util::operations::descriptors::{Descriptor, DescriptorAction};
+use crate::util::operations::descriptors::{Descriptor, DescriptorAction, action_contract};
 use crate::util::register_type::RegisterType;
-use crate::util::runtime_error_str;
-use crate::util::type_check::{ErrorTypes, TypeCheckError, Types};
+use crate::util::runtime_error;
+use crate::util::type_check::Types;
 
 #[derive(Debug)]
 pub struct File {
-    file: Option<StdFile>,
-    reader: Option<Box<BufReader<File>>>,
+    reader: Option<BufReader<StdFile>>,
     path: Option<String>,
 }
 
 impl File {
     pub fn new() -> Self {
-        File { file: None, reader: None, path: None }
+        File { reader: None, path: None }
+    }
+
+    /// Drops read ahead data, so the file position matches what was read
+    fn sync(&mut self) -> Option<&mut StdFile> {
+        let reader = self.reader.as_mut()?;
+        reader.seek(SeekFrom::Current(0)).ok()?;
+        Some(reader.get_mut())
+    }
+
+    fn open(&mut self, path: String, options: &OpenOptions, data: &mut Vec<RegisterType>) {
+        // A failed open leaves the descriptor closed, so the program can check the result
+        let file = options.open(&path).ok();
+        data.push(RegisterType::Bool(file.is_some()));
+        self.reader = file.map(BufReader::new);
+        self.path = Some(path);
     }
 }
 
+/// Failures are pushed as a result, so the program can handle them. Actions on a file which is not open fail as well
 impl Descriptor for File {
     fn action(&mut self, action: DescriptorAction, data: &mut Vec<RegisterType>, info: &OperationDataInfo) {
         match action {
-            DescriptorAction::Open => {
-                let path = data.pop().unwrap();
-                if let RegisterType::String(path) = path {
-                    self.file = Some(OpenOptions::new().read(true).write(true).create(true).open(&path).expect("file to be opened"));
-                    self.path = Some(path);
+            DescriptorAction::Open | DescriptorAction::OpenRead | DescriptorAction::OpenWrite |
+            DescriptorAction::OpenAppend | DescriptorAction::OpenCreate => {
+                if let RegisterType::String(path) = data.pop().unwrap() {
+                    let mut options = OpenOptions::new();
+                    match action {
+                        DescriptorAction::Open => options.read(true).write(true).create(true),
+                        DescriptorAction::OpenRead => options.read(true),
+                        DescriptorAction::OpenWrite => options.write(true).create(true).truncate(true),
+                        DescriptorAction::OpenAppend => options.append(true).create(true),
+                        _ => options.read(true).write(true).create_new(true),
+                    };
+                    self.open(path, &options, data);
                 }
             }
             DescriptorAction::Close => {
-                let file = self.file.as_mut().unwrap();
-                file.flush().unwrap();
-                self.file = None;
+                let flushed = self.reader.take().map_or(true, |mut reader| reader.get_mut().flush().is_ok());
+                data.push(RegisterType::Bool(flushed));
             }
             DescriptorAction::ToString => {
-                let path_orig = self.path.as_ref().unwrap();
-                let mut path = "FileDescriptor(".to_string();
-                path.push_str(path_orig);
-                path.push_str(")");
-                data.push(RegisterType::String(path))
+                let path = self.path.clone().unwrap_or_default();
+                data.push(RegisterType::String(format!("FileDescriptor({})", path)))
             }
             DescriptorAction::ReadAll => {
-                let res = self.file.as_mut().map(|mut f| {
-                    let mut buf = String::new();
-                    f.read_to_string(&mut buf).unwrap();
-                    buf
-                }).unwrap();
-                data.push(RegisterType::String(res))
+                let mut buf = String::new();
+                let read = self.reader.as_mut().map_or(false, |reader| reader.read_to_string(&mut buf).is_ok());
+                data.push(RegisterType::String(buf));
+                data.push(RegisterType::Bool(read));
             }
-            DescriptorAction::WriteAll => {
-                let str = data.pop().unwrap();
-                if let RegisterType::String(str) = str.clone() {
-                    self.file.as_mut().map_or_else(|| {
-                        runtime_error_str("File write failed", info)
-                    }, |f| {
-                        f.write_all(str.as_bytes()).unwrap();
-                    });
+            DescriptorAction::WriteAll | DescriptorAction::WriteLine => {
+                if let RegisterType::String(mut str) = data.pop().unwrap() {
+                    if action == DescriptorAction::WriteLine {
+                        str.push('\n');
+                    }
+
+                    let written = self.sync().map_or(false, |file| file.write_all(str.as_bytes()).is_ok());
+                    data.push(RegisterType::Bool(written));
                 }
             }
-        }
-    }
+            DescriptorAction::ReadLine => {
+                let mut line = String::new();
+                // A failed read is reported like the end of the file, because no line can be read
+                let read = self.reader.as_mut().and_then(|reader| reader.read_line(&mut line).ok()).unwrap_or(0);
 
-    fn typecheck(&self, action: DescriptorAction, stack: &mut Vec<Types>, info: &OperationDataInfo) -> TypeCheckError {
-        match action {
-            DescriptorAction::Open => {
-                if stack.len() == 0 {
-                    ErrorTypes::TooFewElements.into_with_ctx(vec![Types::String], vec![])
-                } else {
-                    let tmp_stack = stack.clone();
-                    let pop = stack.pop().unwrap();
-                    if pop == Types::String {
-                        ErrorTypes::None.into()
-                    } else {
-                        ErrorTypes::InvalidTypes.into_with_ctx(vec![Types::String], tmp_stack)
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
                     }
                 }
+
+                data.push(RegisterType::String(line));
+                data.push(RegisterType::Bool(read == 0));
             }
-            DescriptorAction::Close => {
-                ErrorTypes::None.into()
+            DescriptorAction::Eof => {
+                let eof = self.reader.as_mut().and_then(|reader| reader.fill_buf().ok()).map_or(true, |buf| buf.is_empty());
+                data.push(RegisterType::Bool(eof));
+            }
+            DescriptorAction::Seek => {
+                if let RegisterType::Int(offset) = data.pop().unwrap() {
+                    let moved = u64::try_from(offset).ok().zip(self.reader.as_mut()).map_or(false, |(offset, reader)| {
+                        reader.seek(SeekFrom::Start(offset)).is_ok()
+                    });
+                    data.push(RegisterType::Bool(moved));
+                }
+            }
+            DescriptorAction::Size => {
+                // Files of 2 GiB or more do not fit into an int
+                let size = self.reader.as_ref()
+                    .and_then(|reader| reader.get_ref().metadata().ok())
+                    .and_then(|meta| i32::try_from(meta.len()).ok());
+                data.push(RegisterType::Int(size.unwrap_or(0)));
+                data.push(RegisterType::Bool(size.is_some()));
             }
             _ => {
-                ErrorTypes::ClosureError.into_txt("Tried to typecheck without implementation")
+                runtime_error(format!("The action {:?} is not supported by file", action), info);
             }
         }
     }
 
+    fn is_open(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    fn contract(&self, action: DescriptorAction) -> Option<(Vec<Types>, Vec<Types>)> {
+        match action {
+            DescriptorAction::Open | DescriptorAction::OpenRead | DescriptorAction::OpenWrite |
+            DescriptorAction::OpenAppend | DescriptorAction::OpenCreate | DescriptorAction::ReadAll |
+            DescriptorAction::WriteAll | DescriptorAction::ReadLine | DescriptorAction::WriteLine |
+            DescriptorAction::Eof | DescriptorAction::Seek | DescriptorAction::Size |
+            DescriptorAction::Close => Some(action_contract(action)),
+            _ => None,
+        }
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
-}
\ No newline at end of file
+}
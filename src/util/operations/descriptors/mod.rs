@@ -3,29 +3,78 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use std::lazy::SyncLazy;
 use std::rc::Rc;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 
 use crate::{compiler_error, compiler_error_str, Position, VM};
 use crate::parser::Function;
 use crate::util::{runtime_error, runtime_error_str, runtime_warning_str};
 use crate::util::operation::{Operand, OperationData, OperationDataInfo, OperationType};
 use crate::util::operations::descriptors::file::File;
+use crate::util::operations::descriptors::process::Process;
+use crate::util::operations::descriptors::socket::Socket;
+use crate::util::operations::descriptors::stdio::StdStream;
 use crate::util::register_type::RegisterType;
-use crate::util::type_check::{ErrorTypes, TypeCheckError, Types};
+use crate::util::type_check::{apply_contract, ErrorTypes, TypeCheckError, Types};
 
 mod file;
-
-const DESCRIPTORS_MAP: SyncLazy<HashMap<&'static str, DescriptorType>> = SyncLazy::new(|| {
-    let mut map = HashMap::new();
-    map.insert("file", DescriptorType::File);
-    map
+mod process;
+mod socket;
+mod stdio;
+
+/// Creates a descriptor of a registered type. It is only opened by one of the open actions
+pub type DescriptorConstructor = fn() -> Box<dyn Descriptor>;
+
+/// The descriptor types usable with !<name>-<action>. Embedders add their own with register_descriptor
+static DESCRIPTORS: SyncLazy<RwLock<HashMap<String, DescriptorConstructor>>> = SyncLazy::new(|| {
+    let mut map: HashMap<String, DescriptorConstructor> = HashMap::new();
+    map.insert("file".to_string(), || Box::new(File::new()));
+    map.insert("stdin".to_string(), || Box::new(StdStream::new(DescriptorType::Stdin)));
+    map.insert("stdout".to_string(), || Box::new(StdStream::new(DescriptorType::Stdout)));
+    map.insert("stderr".to_string(), || Box::new(StdStream::new(DescriptorType::Stderr)));
+    map.insert("process".to_string(), || Box::new(Process::new()));
+    map.insert("tcp".to_string(), || Box::new(Socket::new(DescriptorType::Tcp)));
+    map.insert("unix".to_string(), || Box::new(Socket::new(DescriptorType::Unix)));
+    RwLock::new(map)
 });
 
+/// Registers a new descriptor type, which can then be used with !<name>-<action> and rsc<name>.
+/// It has to be registered before the program is parsed. The name must not be empty, contain a '-'
+/// or already be registered
+pub fn register_descriptor(name: &str, constructor: DescriptorConstructor) -> Result<(), String> {
+    if name.is_empty() || name.contains('-') || name.contains(char::is_whitespace) {
+        return Err(format!("Invalid descriptor name '{}'. It must not be empty or contain a '-' or whitespace", name));
+    }
+
+    let mut descriptors = DESCRIPTORS.write().unwrap();
+    if descriptors.contains_key(name) {
+        return Err(format!("The descriptor {} is already registered", name));
+    }
+    descriptors.insert(name.to_string(), constructor);
+    Ok(())
+}
+
 const DESCRIPTOR_ACTION_MAP: SyncLazy<HashMap<&'static str, DescriptorAction>> = SyncLazy::new(|| {
     let mut map = HashMap::new();
     map.insert("open", DescriptorAction::Open);
+    map.insert("open-read", DescriptorAction::OpenRead);
+    map.insert("open-write", DescriptorAction::OpenWrite);
+    map.insert("open-append", DescriptorAction::OpenAppend);
+    map.insert("open-create", DescriptorAction::OpenCreate);
     map.insert("write-all", DescriptorAction::WriteAll);
     map.insert("read-all", DescriptorAction::ReadAll);
+    map.insert("close", DescriptorAction::Close);
+    map.insert("read-line", DescriptorAction::ReadLine);
+    map.insert("write-line", DescriptorAction::WriteLine);
+    map.insert("eof", DescriptorAction::Eof);
+    map.insert("seek", DescriptorAction::Seek);
+    map.insert("size", DescriptorAction::Size);
+    map.insert("open-args", DescriptorAction::OpenArgs);
+    map.insert("read-err-line", DescriptorAction::ReadErrLine);
+    map.insert("read-err-all", DescriptorAction::ReadErrAll);
+    map.insert("wait", DescriptorAction::Wait);
+    map.insert("connect", DescriptorAction::Connect);
+    map.insert("listen", DescriptorAction::Listen);
+    map.insert("accept", DescriptorAction::Accept);
     map
 });
 
@@ -33,36 +82,102 @@ const DESCRIPTOR_ACTION_MAP: SyncLazy<HashMap<&'static str, DescriptorAction>> =
 pub enum DescriptorAction {
     // General Actions
     Open,
+    // Open modes. They push a bool which is false if opening failed
+    /// Opens an existing file for reading
+    OpenRead,
+    /// Opens a file for writing. It is created or truncated
+    OpenWrite,
+    /// Opens a file for writing at its end. It is created if it does not exist
+    OpenAppend,
+    /// Creates a new file for reading and writing. Fails if it already exists
+    OpenCreate,
+    /// Starts a program with a map of arguments instead of a command line
+    OpenArgs,
     ReadAll,
     WriteAll,
     ToString,
     Close,
+    // Line based actions
+    /// Reads the next line without the line break. The bool is true if the end of the file was reached
+    ReadLine,
+    /// Writes the string followed by a line break
+    WriteLine,
+    Eof,
+    /// Moves to the given byte offset from the start
+    Seek,
+    Size,
+    // Process actions
+    /// Reads the next line of stderr. The bool is true if the end was reached
+    ReadErrLine,
+    ReadErrAll,
+    /// Closes stdin and waits for the process to exit. Output which was not read yet is kept.
+    /// Pushes the exit code and whether waiting succeeded
+    Wait,
+    // Socket actions
+    /// Connects to an address. Pushes a bool which is false if connecting failed
+    Connect,
+    /// Listens on an address. Pushes a bool which is false if binding failed
+    Listen,
+    /// Waits for a connection on a listening socket. Pushes it as a new descriptor and whether accepting succeeded
+    Accept,
+    /// An action of a registered descriptor, declared in its custom_actions
+    Custom(&'static str),
 }
 
+impl DescriptorAction {
+    pub fn is_open(&self) -> bool {
+        match self {
+            DescriptorAction::Open | DescriptorAction::OpenRead | DescriptorAction::OpenWrite |
+            DescriptorAction::OpenAppend | DescriptorAction::OpenCreate | DescriptorAction::OpenArgs |
+            DescriptorAction::Connect | DescriptorAction::Listen => true,
+            _ => false,
+        }
+    }
+}
+
+/// The kinds of the built in descriptors which share an implementation
 #[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
 pub enum DescriptorType {
-    File
+    Stdin,
+    Stdout,
+    Stderr,
+    Tcp,
+    Unix,
 }
 
 pub trait Descriptor: Debug {
     fn action(&mut self, action: DescriptorAction, data: &mut Vec<RegisterType>, info: &OperationDataInfo);
-    fn typecheck(&self, action: DescriptorAction, stack: &mut Vec<Types>, info: &OperationDataInfo) -> TypeCheckError;
+    fn is_open(&self) -> bool;
+    /// The inputs and outputs of an action or None if the descriptor does not support it.
+    /// The inputs lie above the descriptor and the outputs are pushed below it
+    fn contract(&self, action: DescriptorAction) -> Option<(Vec<Types>, Vec<Types>)>;
+    /// The names of actions which are not built in. They are passed to action and contract as
+    /// DescriptorAction::Custom and can not open or close the descriptor
+    fn custom_actions(&self) -> Vec<&'static str> {
+        vec![]
+    }
     fn as_any(&self) -> &dyn Any;
 }
 
-fn map_or_error_desc_type(str: &str, is_runtime: bool, info: &OperationDataInfo) -> DescriptorType {
-    if DESCRIPTORS_MAP.contains_key(str) {
-        *DESCRIPTORS_MAP.get(str).unwrap()
+pub fn descriptor_exists(name: &str) -> bool {
+    DESCRIPTORS.read().unwrap().contains_key(name)
+}
+
+fn get_descriptor(str: &str, is_runtime: bool, info: &OperationDataInfo) -> Box<dyn Descriptor> {
+    let constructor = DESCRIPTORS.read().unwrap().get(str).copied();
+    if let Some(constructor) = constructor {
+        constructor()
     } else {
         let error_fn = if is_runtime { runtime_error } else { compiler_error };
         error_fn(format!("The descriptor {} is not registered", str), info);
-        unreachable!()
     }
 }
 
-fn map_or_error_desc_action(str: &str, is_runtime: bool, info: &OperationDataInfo) -> DescriptorAction {
+fn map_or_error_desc_action(descr: &dyn Descriptor, str: &str, is_runtime: bool, info: &OperationDataInfo) -> DescriptorAction {
     if DESCRIPTOR_ACTION_MAP.contains_key(str) {
         *DESCRIPTOR_ACTION_MAP.get(str).unwrap()
+    } else if let Some(custom) = descr.custom_actions().into_iter().find(|custom| *custom == str) {
+        DescriptorAction::Custom(custom)
     } else {
         let error_fn = if is_runtime { runtime_error } else { compiler_error };
         error_fn(format!("The action {} is not registered", str), info);
@@ -70,61 +185,58 @@ fn map_or_error_desc_action(str: &str, is_runtime: bool, info: &OperationDataInf
     }
 }
 
-fn get_descriptor_from_type(typ: DescriptorType) -> Box<dyn Descriptor> {
-    Box::new(match typ {
-        DescriptorType::File => {
-            File::new()
-        }
-    })
+fn get_contract(descr: &dyn Descriptor, name: &str, action: &str, is_runtime: bool, info: &OperationDataInfo) -> (Vec<Types>, Vec<Types>) {
+    if let Some(contract) = descr.contract(map_or_error_desc_action(descr, action, is_runtime, info)) {
+        contract
+    } else {
+        let error_fn = if is_runtime { runtime_error } else { compiler_error };
+        error_fn(format!("The descriptor {} does not support the action {}", name, action), info);
+    }
 }
 
-fn descriptor_typecheck(stack: &mut Vec<Types>, typ: DescriptorType, action: DescriptorAction) -> TypeCheckError {
-    let (inp, outp) = get_descriptor_contract(typ, action);
-    let mut iter = inp.clone();
-    let tmp_stack = stack.clone();
-    for rem in iter {
-        if stack.len() == 0 {
-            return ErrorTypes::TooFewElements.into_with_ctx(inp, vec![]);
-        }
-        let pop = stack.pop().unwrap();
-        if rem != pop {
-            return ErrorTypes::InvalidTypes.into_with_ctx(inp, tmp_stack);
-        }
-    }
+fn descriptor_typecheck(stack: &mut Vec<Types>, name: &str, action: DescriptorAction, mut inp: Vec<Types>, mut outp: Vec<Types>) -> TypeCheckError {
+    let typ = Types::Descriptor(Some(name.to_string()));
 
-    if stack.len() == 0 {
-        let mut tmp_inp = inp;
-        tmp_inp.push(Types::Descriptor);
-        return ErrorTypes::TooFewElements.into_with_ctx(tmp_inp, vec![]);
+    // A plain rsc could be any kind, so the actions of one kind can not be checked on it
+    if !action.is_open() && stack.len() > inp.len() && stack[stack.len() - inp.len() - 1] == Types::Descriptor(None) {
+        return ErrorTypes::Raw(format!("The descriptor is declared as plain rsc, so its kind is unknown. Declare it as rsc<{}> to use its actions", name)).into();
     }
 
-    let pop = stack.pop().unwrap();
-
-    if pop != Types::Descriptor {
-        let mut tmp_inp = inp;
-        tmp_inp.push(Types::Descriptor);
-        return ErrorTypes::TooFewElements.into_with_ctx(tmp_inp, vec![]);
+    // Opening creates the descriptor, every other action needs one of the same kind below its inputs.
+    // Closing consumes it
+    if !action.is_open() {
+        inp.insert(0, typ.clone());
     }
-
-    for out in outp {
-        stack.push(out);
+    if action != DescriptorAction::Close {
+        outp.push(typ);
     }
 
-    stack.push(Types::Descriptor);
-
-    ErrorTypes::None.into()
+    apply_contract(&inp, &outp, stack)
 }
 
-fn get_descriptor_contract(typ: DescriptorType, action: DescriptorAction) -> (Vec<Types>, Vec<Types>) {
+/// The contract most descriptors use for an action
+pub fn action_contract(action: DescriptorAction) -> (Vec<Types>, Vec<Types>) {
     match action {
-        DescriptorAction::Open => unreachable!(),
-        DescriptorAction::ReadAll => (vec![], vec![Types::String]),
-        DescriptorAction::WriteAll => (vec![Types::String], vec![]),
-        DescriptorAction::ToString => (vec![Types::String], vec![]),
-        DescriptorAction::Close => unreachable!(),
-        _ => match typ {
-            _ => unreachable!()
-        }
+        DescriptorAction::Open | DescriptorAction::OpenRead | DescriptorAction::OpenWrite |
+        DescriptorAction::OpenAppend | DescriptorAction::OpenCreate => (vec![Types::String], vec![Types::Bool]),
+        DescriptorAction::OpenArgs => (vec![Types::String, Types::Map(Box::new(Types::Int), Box::new(Types::String))], vec![Types::Bool]),
+        // The bool is false if the action failed
+        DescriptorAction::ReadAll => (vec![], vec![Types::String, Types::Bool]),
+        DescriptorAction::WriteAll => (vec![Types::String], vec![Types::Bool]),
+        DescriptorAction::ToString => (vec![], vec![Types::String]),
+        DescriptorAction::Close => (vec![], vec![Types::Bool]),
+        DescriptorAction::ReadLine => (vec![], vec![Types::String, Types::Bool]),
+        DescriptorAction::WriteLine => (vec![Types::String], vec![Types::Bool]),
+        DescriptorAction::Eof => (vec![], vec![Types::Bool]),
+        DescriptorAction::Seek => (vec![Types::Int], vec![Types::Bool]),
+        DescriptorAction::Size => (vec![], vec![Types::Int, Types::Bool]),
+        DescriptorAction::ReadErrLine => (vec![], vec![Types::String, Types::Bool]),
+        DescriptorAction::ReadErrAll => (vec![], vec![Types::String, Types::Bool]),
+        DescriptorAction::Wait => (vec![], vec![Types::Int, Types::Bool]),
+        DescriptorAction::Connect | DescriptorAction::Listen => (vec![Types::String], vec![Types::Bool]),
+        DescriptorAction::Accept => (vec![], vec![Types::Descriptor(None), Types::Bool]),
+        // Custom actions have no shared meaning, their descriptor has to declare them
+        DescriptorAction::Custom(_) => (vec![], vec![]),
     }
 }
 
@@ -161,22 +273,31 @@ pub fn execute_fn() -> Box<dyn Fn(&OperationData, &mut VM)> {
     Box::new(|op_data, vm| {
         if let OperationType::Descriptor = op_data.typ {
             if let Operand::DescriptorAction(descr, action) = op_data.clone().operand.unwrap() {
-                let typ = map_or_error_desc_type(&descr, true, &op_data.data);
-                let action = map_or_error_desc_action(&action, true, &op_data.data);
-
-                if action == DescriptorAction::Close {
-                    runtime_warning_str("Closing descriptor is not allowed via a descriptor action", &op_data.data);
-                }
-
-
-                if action == DescriptorAction::Open {
-                    let mut descr: Box<dyn Descriptor> = get_descriptor_from_type(typ);
-                    descr.action(DescriptorAction::Open, vm.stack_mut(), &op_data.data);
-                    vm.stack_mut().push(RegisterType::Descriptor(Rc::new(Mutex::new(descr))))
+                let name = descr;
+                let action_name = action;
+                let mut descr = get_descriptor(&name, true, &op_data.data);
+                let action = map_or_error_desc_action(descr.as_ref(), &action_name, true, &op_data.data);
+
+                if action.is_open() {
+                    descr.action(action, vm.stack_mut(), &op_data.data);
+                    // Only open descriptors are tracked for the leak warning, so failed opens are not kept alive
+                    let open = descr.is_open();
+                    let descr = Rc::new(Mutex::new(descr));
+                    if open {
+                        vm.descriptors_mut().push(descr.clone());
+                    }
+                    vm.stack_mut().push(RegisterType::Descriptor(descr))
+                } else if action == DescriptorAction::Close {
+                    // Closing consumes the descriptor
+                    if let Some(RegisterType::Descriptor(descr)) = vm.stack_mut().pop() {
+                        descr.lock().as_mut().unwrap().action(action, vm.stack_mut(), &op_data.data);
+                        // Closed descriptors are no longer tracked, so opening them in a loop does not grow memory
+                        vm.descriptors_mut().retain(|other| !Rc::ptr_eq(other, &descr));
+                    }
                 } else {
                     let stack = vm.stack_mut();
 
-                    let (inp, _) = get_descriptor_contract(typ, action);
+                    let (inp, _) = get_contract(descr.as_ref(), &name, &action_name, true, &op_data.data);
                     let inp = inp.len();
 
                     let mut tmp_stack = vec![];
@@ -192,9 +313,18 @@ pub fn execute_fn() -> Box<dyn Fn(&OperationData, &mut VM)> {
                             let push = descr.clone();
                             let mut lock = descr.lock();
                             let lock = lock.as_mut().unwrap();
+                            let outputs = stack.len();
                             stack.extend(tmp_stack);
                             lock.action(action, stack, &op_data.data);
+
+                            // Descriptors an action pushes, like accepted connections, have to be closed as well
+                            let created = stack[outputs..].iter().filter_map(|output| match output {
+                                RegisterType::Descriptor(created) if created.lock().unwrap().is_open() => Some(created.clone()),
+                                _ => None,
+                            }).collect::<Vec<_>>();
+
                             stack.push(RegisterType::Descriptor(push));
+                            vm.descriptors_mut().extend(created);
                         }
                     }
                 }
@@ -207,32 +337,12 @@ pub fn execute_fn() -> Box<dyn Fn(&OperationData, &mut VM)> {
 pub fn type_check_fn() -> Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError> {
     Box::new(|op_data, fns, stack, compile_time| {
         if let OperationType::Descriptor = op_data.typ {
-            if let Operand::DescriptorAction(descr, action) = op_data.clone().operand.unwrap() {
-                let typ = map_or_error_desc_type(&descr, true, &op_data.data);
-                let action = map_or_error_desc_action(&action, true, &op_data.data);
+            if let Operand::DescriptorAction(name, action_name) = op_data.clone().operand.unwrap() {
+                let descr = get_descriptor(&name, !compile_time, &op_data.data);
+                let action = map_or_error_desc_action(descr.as_ref(), &action_name, !compile_time, &op_data.data);
+                let (inp, outp) = get_contract(descr.as_ref(), &name, &action_name, !compile_time, &op_data.data);
 
-                if stack.len() == 0 {
-                    ErrorTypes::TooFewElements.into()
-                } else {
-                    let res = match action {
-                        DescriptorAction::Open => {
-                            let descr = get_descriptor_from_type(typ);
-                            let success = descr.typecheck(DescriptorAction::Open, stack, &op_data.data);
-                            stack.push(Types::Descriptor);
-                            success
-                        }
-                        DescriptorAction::ToString | DescriptorAction::ReadAll | DescriptorAction::WriteAll => descriptor_typecheck(stack, typ, action),
-                        DescriptorAction::Close => {
-                            ErrorTypes::ClosureError.into_txt("Closing descriptor is not allowed via a descriptor action")
-                        }
-                        _ => {
-                            let descr = get_descriptor_from_type(typ);
-                            descr.typecheck(action, stack, &op_data.data)
-                        }
-                    };
-
-                    res
-                }
+                descriptor_typecheck(stack, &name, action, inp, outp)
             } else {
                 ErrorTypes::ClosureError.into()
             }
@@ -240,4 +350,90 @@ pub fn type_check_fn() -> Box<dyn Fn(&OperationData, &HashMap<String, Function>,
             ErrorTypes::ClosureError.into()
         }
     })
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A descriptor counting how often its custom action was used
+    #[derive(Debug)]
+    struct Counter {
+        open: bool,
+        count: i32,
+    }
+
+    impl Descriptor for Counter {
+        fn action(&mut self, action: DescriptorAction, data: &mut Vec<RegisterType>, _info: &OperationDataInfo) {
+            match action {
+                DescriptorAction::Open => {
+                    self.open = true;
+                    data.push(RegisterType::Bool(true));
+                }
+                DescriptorAction::Custom("count") => {
+                    self.count += 1;
+                    data.push(RegisterType::Int(self.count));
+                }
+                DescriptorAction::Close => {
+                    self.open = false;
+                    data.push(RegisterType::Bool(true));
+                }
+                _ => {}
+            }
+        }
+
+        fn is_open(&self) -> bool {
+            self.open
+        }
+
+        fn contract(&self, action: DescriptorAction) -> Option<(Vec<Types>, Vec<Types>)> {
+            match action {
+                DescriptorAction::Open => Some((vec![], vec![Types::Bool])),
+                DescriptorAction::Custom("count") => Some((vec![], vec![Types::Int])),
+                DescriptorAction::Close => Some(action_contract(action)),
+                _ => None,
+            }
+        }
+
+        fn custom_actions(&self) -> Vec<&'static str> {
+            vec!["count"]
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    fn counter() -> Box<dyn Descriptor> {
+        Box::new(Counter { open: false, count: 0 })
+    }
+
+    #[test]
+    fn registered_descriptors_declare_custom_actions() {
+        assert_eq!(register_descriptor("counter", counter), Ok(()));
+        assert!(descriptor_exists("counter"));
+
+        let mut descr = get_descriptor("counter", true, &OperationDataInfo::None);
+        let action = map_or_error_desc_action(descr.as_ref(), "count", true, &OperationDataInfo::None);
+        assert_eq!(action, DescriptorAction::Custom("count"));
+
+        let (inp, outp) = get_contract(descr.as_ref(), "counter", "count", true, &OperationDataInfo::None);
+        let mut stack = vec![Types::Descriptor(Some("counter".to_string()))];
+        assert!(!descriptor_typecheck(&mut stack, "counter", action, inp, outp).is_error());
+        assert_eq!(stack, vec![Types::Int, Types::Descriptor(Some("counter".to_string()))]);
+
+        let mut data = vec![];
+        descr.action(action, &mut data, &OperationDataInfo::None);
+        descr.action(action, &mut data, &OperationDataInfo::None);
+        assert!(matches!(data.as_slice(), [RegisterType::Int(1), RegisterType::Int(2)]));
+    }
+
+    #[test]
+    fn invalid_and_duplicate_names_are_rejected() {
+        assert!(register_descriptor("", counter).is_err());
+        assert!(register_descriptor("my-counter", counter).is_err());
+        assert!(register_descriptor("my counter", counter).is_err());
+        assert!(register_descriptor("file", counter).is_err());
+        assert!(!descriptor_exists("my-counter"));
+    }
+}
@@ -0,0 +1,197 @@
+use std::any::Any;
+use std::fmt::Debug;
+use std::io::{BufRead, BufReader, Cursor, Read, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::thread;
+
+use crate::util::operation::OperationDataInfo;
+use crate::util::operations::descriptors::{Descriptor, DescriptorAction, action_contract};
+use crate::util::register_type::RegisterType;
+use crate::util::{runtime_error, runtime_error_str};
+use crate::util::type_check::Types;
+
+/// The stdout or stderr of a child. After waiting it holds the output which was not read yet
+trait Output: BufRead + Debug + Send {}
+
+impl<T: BufRead + Debug + Send> Output for T {}
+
+/// A spawned child process. Its stdin, stdout and stderr are piped
+#[derive(Debug)]
+pub struct Process {
+    child: Option<Child>,
+    stdin: Option<ChildStdin>,
+    stdout: Option<Box<dyn Output>>,
+    stderr: Option<Box<dyn Output>>,
+    command: Option<String>,
+}
+
+impl Process {
+    pub fn new() -> Self {
+        Process { child: None, stdin: None, stdout: None, stderr: None, command: None }
+    }
+
+    fn spawn(&mut self, program: String, args: Vec<String>) -> bool {
+        let child = Command::new(&program)
+            .args(&args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+
+        self.command = Some(std::iter::once(program).chain(args).collect::<Vec<_>>().join(" "));
+
+        if let Ok(mut child) = child {
+            self.stdin = child.stdin.take();
+            self.stdout = child.stdout.take().map(|stdout| Box::new(BufReader::new(stdout)) as Box<dyn Output>);
+            self.stderr = child.stderr.take().map(|stderr| Box::new(BufReader::new(stderr)) as Box<dyn Output>);
+            self.child = Some(child);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Reads the rest of stdout and stderr into memory, so a child writing more than the pipes hold can exit.
+    /// Both are read at once, because the child may block on either of them
+    fn drain(&mut self) {
+        let stderr = self.stderr.take().map(|mut stderr| thread::spawn(move || {
+            let mut buf = vec![];
+            let _ = stderr.read_to_end(&mut buf);
+            buf
+        }));
+
+        let mut stdout = vec![];
+        if let Some(mut reader) = self.stdout.take() {
+            let _ = reader.read_to_end(&mut stdout);
+        }
+
+        self.stdout = Some(Box::new(Cursor::new(stdout)));
+        self.stderr = stderr.map(|handle| Box::new(Cursor::new(handle.join().unwrap_or_default())) as Box<dyn Output>);
+    }
+
+    /// Closes the stdin of the child, so it sees the end of its input, and waits for it to exit.
+    /// None if the process is not running or waiting failed
+    fn wait(&mut self) -> Option<i32> {
+        self.stdin = None;
+        if self.child.is_none() {
+            return None;
+        }
+
+        self.drain();
+        let status = self.child.as_mut()?.wait().ok()?;
+        // Processes killed by a signal have no exit code
+        Some(status.code().unwrap_or(-1))
+    }
+}
+
+fn read_line(reader: Option<&mut Box<dyn Output>>, data: &mut Vec<RegisterType>) {
+    let mut line = String::new();
+    // A failed read is reported like the end of the output, because no line can be read
+    let read = reader.and_then(|reader| reader.read_line(&mut line).ok()).unwrap_or(0);
+
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+
+    data.push(RegisterType::String(line));
+    data.push(RegisterType::Bool(read == 0));
+}
+
+fn read_all(reader: Option<&mut Box<dyn Output>>, data: &mut Vec<RegisterType>) {
+    let mut buf = String::new();
+    let read = reader.map_or(false, |reader| reader.read_to_string(&mut buf).is_ok());
+    data.push(RegisterType::String(buf));
+    data.push(RegisterType::Bool(read));
+}
+
+/// Failures are pushed as a result like for files
+impl Descriptor for Process {
+    fn action(&mut self, action: DescriptorAction, data: &mut Vec<RegisterType>, info: &OperationDataInfo) {
+        match action {
+            DescriptorAction::Open => {
+                if let RegisterType::String(command) = data.pop().unwrap() {
+                    let mut parts = command.split_whitespace().map(|part| part.to_string());
+                    // An empty command can not be started
+                    let success = parts.next().map_or(false, |program| self.spawn(program, parts.collect()));
+                    data.push(RegisterType::Bool(success));
+                }
+            }
+            DescriptorAction::OpenArgs => {
+                let args = data.pop().unwrap();
+                let program = data.pop().unwrap();
+                if let (RegisterType::String(program), RegisterType::Map(_, _, args)) = (program, args) {
+                    let args = args.into_values().map(|arg| {
+                        if let RegisterType::String(arg) = arg {
+                            arg
+                        } else {
+                            runtime_error_str("The arguments of a process have to be strings", info)
+                        }
+                    }).collect();
+                    let success = self.spawn(program, args);
+                    data.push(RegisterType::Bool(success));
+                }
+            }
+            DescriptorAction::Close => {
+                // A process which was never started has nothing to wait for
+                let waited = self.child.is_none() || self.wait().is_some();
+                self.child = None;
+                self.stdout = None;
+                self.stderr = None;
+                data.push(RegisterType::Bool(waited));
+            }
+            DescriptorAction::ToString => {
+                let command = self.command.clone().unwrap_or_default();
+                data.push(RegisterType::String(format!("ProcessDescriptor({})", command)))
+            }
+            DescriptorAction::WriteAll | DescriptorAction::WriteLine => {
+                if let RegisterType::String(mut str) = data.pop().unwrap() {
+                    if action == DescriptorAction::WriteLine {
+                        str.push('\n');
+                    }
+
+                    let written = self.stdin.as_mut().map_or(false, |stdin| {
+                        stdin.write_all(str.as_bytes()).and_then(|_| stdin.flush()).is_ok()
+                    });
+                    data.push(RegisterType::Bool(written));
+                }
+            }
+            DescriptorAction::ReadLine => read_line(self.stdout.as_mut(), data),
+            DescriptorAction::ReadAll => read_all(self.stdout.as_mut(), data),
+            DescriptorAction::ReadErrLine => read_line(self.stderr.as_mut(), data),
+            DescriptorAction::ReadErrAll => read_all(self.stderr.as_mut(), data),
+            DescriptorAction::Eof => {
+                let eof = self.stdout.as_mut().and_then(|stdout| stdout.fill_buf().ok()).map_or(true, |buf| buf.is_empty());
+                data.push(RegisterType::Bool(eof));
+            }
+            DescriptorAction::Wait => {
+                let code = self.wait();
+                data.push(RegisterType::Int(code.unwrap_or(-1)));
+                data.push(RegisterType::Bool(code.is_some()));
+            }
+            _ => {
+                runtime_error(format!("The action {:?} is not supported by process", action), info);
+            }
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.child.is_some()
+    }
+
+    fn contract(&self, action: DescriptorAction) -> Option<(Vec<Types>, Vec<Types>)> {
+        match action {
+            DescriptorAction::Open | DescriptorAction::OpenArgs | DescriptorAction::ReadAll |
+            DescriptorAction::WriteAll | DescriptorAction::ReadLine | DescriptorAction::WriteLine |
+            DescriptorAction::ReadErrLine | DescriptorAction::ReadErrAll | DescriptorAction::Eof |
+            DescriptorAction::Wait | DescriptorAction::Close => Some(action_contract(action)),
+            _ => None,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
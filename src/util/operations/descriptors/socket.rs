@@ -0,0 +1,305 @@
+use std::any::Any;
+use std::fmt::Debug;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::rc::Rc;
+use std::sync::Mutex;
+
+use crate::util::operation::OperationDataInfo;
+use crate::util::operations::descriptors::{Descriptor, DescriptorAction, DescriptorType, action_contract};
+use crate::util::register_type::RegisterType;
+use crate::util::runtime_error;
+use crate::util::type_check::Types;
+
+/// A connected socket which can be read and written
+trait Stream: Read + Write + Debug {
+    fn shutdown(&self) -> bool;
+}
+
+impl Stream for TcpStream {
+    fn shutdown(&self) -> bool {
+        TcpStream::shutdown(self, Shutdown::Both).is_ok()
+    }
+}
+
+#[cfg(unix)]
+impl Stream for UnixStream {
+    fn shutdown(&self) -> bool {
+        UnixStream::shutdown(self, Shutdown::Both).is_ok()
+    }
+}
+
+#[derive(Debug)]
+enum Inner {
+    Closed,
+    TcpListener(TcpListener),
+    #[cfg(unix)]
+    UnixListener(UnixListener),
+    Stream(BufReader<Box<dyn Stream>>),
+}
+
+/// A tcp or unix socket. It is either a listener, which accepts connections, or a connection
+#[derive(Debug)]
+pub struct Socket {
+    typ: DescriptorType,
+    inner: Inner,
+    address: Option<String>,
+}
+
+impl Socket {
+    pub fn new(typ: DescriptorType) -> Self {
+        Socket { typ, inner: Inner::Closed, address: None }
+    }
+
+    fn name(&self) -> &'static str {
+        match self.typ {
+            DescriptorType::Tcp => "tcp",
+            _ => "unix",
+        }
+    }
+
+    fn connect(&mut self, address: &str) -> Option<Inner> {
+        let stream: Box<dyn Stream> = match self.typ {
+            DescriptorType::Tcp => {
+                let stream = TcpStream::connect(address).ok()?;
+                self.address = stream.peer_addr().ok().map(|addr| addr.to_string());
+                Box::new(stream)
+            }
+            #[cfg(unix)]
+            _ => Box::new(UnixStream::connect(address).ok()?),
+            #[cfg(not(unix))]
+            _ => return None,
+        };
+        Some(Inner::Stream(BufReader::new(stream)))
+    }
+
+    fn listen(&mut self, address: &str) -> Option<Inner> {
+        match self.typ {
+            DescriptorType::Tcp => {
+                let listener = TcpListener::bind(address).ok()?;
+                // Binding to port 0 picks a free port, so the real address is stored
+                self.address = listener.local_addr().ok().map(|addr| addr.to_string());
+                Some(Inner::TcpListener(listener))
+            }
+            #[cfg(unix)]
+            _ => Some(Inner::UnixListener(UnixListener::bind(address).ok()?)),
+            #[cfg(not(unix))]
+            _ => None,
+        }
+    }
+
+    /// The accepted connection. It is closed if accepting failed
+    fn accept(&mut self) -> Socket {
+        let accepted: Option<(Box<dyn Stream>, Option<String>)> = match &self.inner {
+            Inner::TcpListener(listener) => listener.accept().ok().map(|(stream, addr)| {
+                (Box::new(stream) as Box<dyn Stream>, Some(addr.to_string()))
+            }),
+            #[cfg(unix)]
+            Inner::UnixListener(listener) => listener.accept().ok().map(|(stream, _)| {
+                (Box::new(stream) as Box<dyn Stream>, self.address.clone())
+            }),
+            _ => None,
+        };
+
+        match accepted {
+            Some((stream, address)) => Socket { typ: self.typ, inner: Inner::Stream(BufReader::new(stream)), address },
+            None => Socket::new(self.typ),
+        }
+    }
+
+    fn stream(&mut self) -> Option<&mut BufReader<Box<dyn Stream>>> {
+        if let Inner::Stream(stream) = &mut self.inner {
+            Some(stream)
+        } else {
+            None
+        }
+    }
+}
+
+/// Failures are pushed as a result like for files. Actions on a socket which is not connected fail as well
+impl Descriptor for Socket {
+    fn action(&mut self, action: DescriptorAction, data: &mut Vec<RegisterType>, info: &OperationDataInfo) {
+        match action {
+            DescriptorAction::Connect | DescriptorAction::Listen => {
+                if let RegisterType::String(address) = data.pop().unwrap() {
+                    self.address = Some(address.clone());
+                    let inner = if action == DescriptorAction::Connect {
+                        self.connect(&address)
+                    } else {
+                        self.listen(&address)
+                    };
+
+                    // A failed connect leaves the descriptor closed, so the program can check the result
+                    data.push(RegisterType::Bool(inner.is_some()));
+                    self.inner = inner.unwrap_or(Inner::Closed);
+                }
+            }
+            DescriptorAction::Accept => {
+                let socket = self.accept();
+                let accepted = socket.is_open();
+                let socket: Box<dyn Descriptor> = Box::new(socket);
+                data.push(RegisterType::Descriptor(Rc::new(Mutex::new(socket))));
+                data.push(RegisterType::Bool(accepted));
+            }
+            DescriptorAction::Close => {
+                let closed = match std::mem::replace(&mut self.inner, Inner::Closed) {
+                    Inner::Stream(stream) => stream.get_ref().shutdown(),
+                    // The socket file of a unix listener is removed, so the path can be bound again
+                    #[cfg(unix)]
+                    Inner::UnixListener(_) => std::fs::remove_file(self.address.as_ref().unwrap()).is_ok(),
+                    _ => true,
+                };
+                data.push(RegisterType::Bool(closed));
+            }
+            DescriptorAction::ToString => {
+                let address = self.address.clone().unwrap_or_default();
+                data.push(RegisterType::String(format!("SocketDescriptor({} {})", self.name(), address)))
+            }
+            DescriptorAction::ReadAll => {
+                let mut buf = String::new();
+                let read = self.stream().map_or(false, |stream| stream.read_to_string(&mut buf).is_ok());
+                data.push(RegisterType::String(buf));
+                data.push(RegisterType::Bool(read));
+            }
+            DescriptorAction::WriteAll | DescriptorAction::WriteLine => {
+                if let RegisterType::String(mut str) = data.pop().unwrap() {
+                    if action == DescriptorAction::WriteLine {
+                        str.push('\n');
+                    }
+
+                    let written = self.stream().map_or(false, |stream| {
+                        let stream = stream.get_mut();
+                        stream.write_all(str.as_bytes()).and_then(|_| stream.flush()).is_ok()
+                    });
+                    data.push(RegisterType::Bool(written));
+                }
+            }
+            DescriptorAction::ReadLine => {
+                let mut line = String::new();
+                // A failed read is reported like the end of the connection, because no line can be read
+                let read = self.stream().and_then(|stream| stream.read_line(&mut line).ok()).unwrap_or(0);
+
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+
+                data.push(RegisterType::String(line));
+                data.push(RegisterType::Bool(read == 0));
+            }
+            DescriptorAction::Eof => {
+                let eof = self.stream().and_then(|stream| stream.fill_buf().ok()).map_or(true, |buf| buf.is_empty());
+                data.push(RegisterType::Bool(eof));
+            }
+            _ => {
+                runtime_error(format!("The action {:?} is not supported by {}", action, self.name()), info);
+            }
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        !matches!(self.inner, Inner::Closed)
+    }
+
+    fn contract(&self, action: DescriptorAction) -> Option<(Vec<Types>, Vec<Types>)> {
+        match action {
+            // Accepted connections are of the same kind as the listener. The bool is false if accepting failed
+            DescriptorAction::Accept => Some((vec![], vec![Types::Descriptor(Some(self.name().to_string())), Types::Bool])),
+            DescriptorAction::Connect | DescriptorAction::Listen | DescriptorAction::ReadAll |
+            DescriptorAction::WriteAll | DescriptorAction::ReadLine | DescriptorAction::WriteLine |
+            DescriptorAction::Eof | DescriptorAction::Close => Some(action_contract(action)),
+            _ => None,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pop_bool(data: &mut Vec<RegisterType>) -> bool {
+        match data.pop() {
+            Some(RegisterType::Bool(value)) => value,
+            other => panic!("Expected a bool, got {:?}", other),
+        }
+    }
+
+    fn pop_string(data: &mut Vec<RegisterType>) -> String {
+        match data.pop() {
+            Some(RegisterType::String(value)) => value,
+            other => panic!("Expected a string, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tcp_round_trip_on_localhost() {
+        let info = OperationDataInfo::None;
+        let mut data = vec![];
+
+        // Port 0 lets the system pick a free port, the listener stores the real address
+        let mut listener = Socket::new(DescriptorType::Tcp);
+        data.push(RegisterType::String("127.0.0.1:0".to_string()));
+        listener.action(DescriptorAction::Listen, &mut data, &info);
+        assert!(pop_bool(&mut data));
+        let address = listener.address.clone().unwrap();
+
+        let mut client = Socket::new(DescriptorType::Tcp);
+        data.push(RegisterType::String(address));
+        client.action(DescriptorAction::Connect, &mut data, &info);
+        assert!(pop_bool(&mut data));
+
+        listener.action(DescriptorAction::Accept, &mut data, &info);
+        assert!(pop_bool(&mut data));
+        let server = match data.pop() {
+            Some(RegisterType::Descriptor(server)) => server,
+            other => panic!("Expected a descriptor, got {:?}", other),
+        };
+        let mut server = server.lock().unwrap();
+
+        data.push(RegisterType::String("Hello".to_string()));
+        client.action(DescriptorAction::WriteLine, &mut data, &info);
+        assert!(pop_bool(&mut data));
+
+        server.action(DescriptorAction::ReadLine, &mut data, &info);
+        assert!(!pop_bool(&mut data));
+        assert_eq!(pop_string(&mut data), "Hello");
+
+        data.push(RegisterType::String("Bye".to_string()));
+        server.action(DescriptorAction::WriteAll, &mut data, &info);
+        assert!(pop_bool(&mut data));
+        server.action(DescriptorAction::Close, &mut data, &info);
+        assert!(pop_bool(&mut data));
+
+        // The server closed the connection, so reading everything ends
+        client.action(DescriptorAction::ReadAll, &mut data, &info);
+        assert!(pop_bool(&mut data));
+        assert_eq!(pop_string(&mut data), "Bye");
+
+        client.action(DescriptorAction::Close, &mut data, &info);
+        listener.action(DescriptorAction::Close, &mut data, &info);
+        assert!(data.drain(..).all(|closed| matches!(closed, RegisterType::Bool(true))));
+    }
+
+    #[test]
+    fn actions_on_a_closed_socket_fail() {
+        let info = OperationDataInfo::None;
+        let mut data = vec![];
+        let mut socket = Socket::new(DescriptorType::Tcp);
+
+        data.push(RegisterType::String("Hello".to_string()));
+        socket.action(DescriptorAction::WriteLine, &mut data, &info);
+        assert!(!pop_bool(&mut data));
+
+        socket.action(DescriptorAction::ReadLine, &mut data, &info);
+        assert!(pop_bool(&mut data));
+        assert_eq!(pop_string(&mut data), "");
+    }
+}
@@ -0,0 +1,120 @@
+use std::any::Any;
+use std::io::{BufRead, Read, stderr, stdin, stdout, Write};
+
+use crate::util::operation::OperationDataInfo;
+use crate::util::operations::descriptors::{Descriptor, DescriptorAction, DescriptorType, action_contract};
+use crate::util::register_type::RegisterType;
+use crate::util::runtime_error;
+use crate::util::type_check::Types;
+
+/// The standard streams of the process. Opening them takes no arguments
+#[derive(Debug)]
+pub struct StdStream {
+    typ: DescriptorType,
+    open: bool,
+}
+
+impl StdStream {
+    pub fn new(typ: DescriptorType) -> Self {
+        StdStream { typ, open: false }
+    }
+
+    fn name(&self) -> &'static str {
+        match self.typ {
+            DescriptorType::Stdin => "stdin",
+            DescriptorType::Stdout => "stdout",
+            _ => "stderr",
+        }
+    }
+
+    fn write(&self, str: &str) -> bool {
+        let written = match self.typ {
+            DescriptorType::Stdout => {
+                let mut out = stdout();
+                out.write_all(str.as_bytes()).and_then(|_| out.flush())
+            }
+            _ => stderr().write_all(str.as_bytes()),
+        };
+
+        written.is_ok()
+    }
+}
+
+/// Failures are pushed as a result like for files. Actions on a stream which is not open fail as well
+impl Descriptor for StdStream {
+    fn action(&mut self, action: DescriptorAction, data: &mut Vec<RegisterType>, info: &OperationDataInfo) {
+        match action {
+            DescriptorAction::Open => {
+                self.open = true;
+                data.push(RegisterType::Bool(true));
+            }
+            DescriptorAction::Close => {
+                let flushed = self.typ != DescriptorType::Stdout || stdout().flush().is_ok();
+                data.push(RegisterType::Bool(self.open && flushed));
+                self.open = false;
+            }
+            DescriptorAction::ToString => {
+                data.push(RegisterType::String(format!("StreamDescriptor({})", self.name())))
+            }
+            DescriptorAction::WriteAll | DescriptorAction::WriteLine => {
+                if let RegisterType::String(mut str) = data.pop().unwrap() {
+                    if action == DescriptorAction::WriteLine {
+                        str.push('\n');
+                    }
+                    data.push(RegisterType::Bool(self.open && self.write(&str)));
+                }
+            }
+            DescriptorAction::ReadAll if self.typ == DescriptorType::Stdin => {
+                let mut buf = String::new();
+                let read = self.open && stdin().read_to_string(&mut buf).is_ok();
+                data.push(RegisterType::String(buf));
+                data.push(RegisterType::Bool(read));
+            }
+            DescriptorAction::ReadLine if self.typ == DescriptorType::Stdin => {
+                let mut line = String::new();
+                // A failed read is reported like the end of the input, because no line can be read
+                let read = if self.open { stdin().read_line(&mut line).unwrap_or(0) } else { 0 };
+
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+
+                data.push(RegisterType::String(line));
+                data.push(RegisterType::Bool(read == 0));
+            }
+            DescriptorAction::Eof if self.typ == DescriptorType::Stdin => {
+                let eof = !self.open || stdin().lock().fill_buf().map_or(true, |buf| buf.is_empty());
+                data.push(RegisterType::Bool(eof));
+            }
+            _ => {
+                runtime_error(format!("The action {:?} is not supported by {}", action, self.name()), info);
+            }
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn contract(&self, action: DescriptorAction) -> Option<(Vec<Types>, Vec<Types>)> {
+        match (self.typ, action) {
+            // Opening a stream takes no arguments and always succeeds
+            (_, DescriptorAction::Open) => Some((vec![], vec![Types::Bool])),
+            (_, DescriptorAction::Close) => Some(action_contract(action)),
+            (DescriptorType::Stdin, DescriptorAction::ReadAll | DescriptorAction::ReadLine | DescriptorAction::Eof) => {
+                Some(action_contract(action))
+            }
+            (DescriptorType::Stdout | DescriptorType::Stderr, DescriptorAction::WriteAll | DescriptorAction::WriteLine) => {
+                Some(action_contract(action))
+            }
+            _ => None,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
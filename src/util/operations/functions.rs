@@ -3,7 +3,7 @@ pub mod typecheck {
 
     use crate::parser::Function;
     use crate::util::operation::{Operand, OperationData};
-    use crate::util::type_check::{ErrorTypes, TypeCheckError, Types};
+    use crate::util::type_check::{apply_contract, check_contract, copies_descriptor, ErrorTypes, TypeCheckError, Types};
 
     pub fn create_calling_type_check() -> Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError> {
         Box::new(|data, fns, stack, compile_time| {
@@ -40,45 +40,13 @@ pub mod typecheck {
             };
 
             if success.error == ErrorTypes::None {
-                if stack.len() >= inp.len() {
-                    let success = {
-                        if inp.len() == 0 {
-                            true
-                        } else {
-                            let mut tmp_inp = inp.clone();
-                            let mut tmp_stack = stack.clone();
-                            if inp.len() <= tmp_stack.len() {
-                                let mut success = true;
-                                while tmp_inp.len() > 0 {
-                                    let a = tmp_inp.pop().unwrap();
-                                    let b = tmp_stack.pop().unwrap();
-                                    if a != b {
-                                        success = false;
-                                        break;
-                                    }
-                                }
-                                success
-                            } else {
-                                false
-                            }
-                        }
-                    };
-
-                    if !success {
-                        ErrorTypes::InvalidTypes.into_with_ctx(inp, stack.clone())
-                    } else {
-                        if compile_time {
-                            for _ in 0..inp.len() {
-                                stack.pop().unwrap();
-                            }
-                            for typ in outp {
-                                stack.push(typ.clone());
-                            }
-                        }
-                        ErrorTypes::None.into()
-                    }
+                // Generic functions could return a descriptor bound to a type variable more than once
+                if copies_descriptor(&inp, &outp, stack) {
+                    ErrorTypes::Raw("Descriptors can not be copied. Close them with the close action instead".to_string()).into()
+                } else if compile_time {
+                    apply_contract(&inp, &outp, stack)
                 } else {
-                    ErrorTypes::TooFewElements.into_with_ctx_plus(vec![Types::FunctionPointer(vec![Types::Any], vec![Types::Any])], stack.clone(), "Function pointer can contain any amount/sort of types")
+                    check_contract(&inp, stack).map_or_else(|err| err, |_| ErrorTypes::None.into())
                 }
             } else {
                 success
@@ -144,7 +112,13 @@ pub mod runtime {
                 }
 
                 let fnc = vm.ops().get(&fnc).unwrap().clone();
-                if (inp, outp) == fnc.get_contract() {
+                // The pointer may declare a wider type, like rsc for a function returning rsc<file>
+                let (fn_inp, fn_outp) = fnc.get_contract();
+                let compatible = inp.len() == fn_inp.len() && outp.len() == fn_outp.len()
+                    && fn_inp.iter().zip(&inp).all(|(wanted, got)| wanted.accepts(got))
+                    && outp.iter().zip(&fn_outp).all(|(wanted, got)| wanted.accepts(got));
+
+                if compatible {
                     vm.execute_fn(&fnc);
                 } else {
                     runtime_error_str("Typecheck for dynamic function call failed", info);
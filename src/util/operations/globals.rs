@@ -0,0 +1,68 @@
+pub mod typecheck {
+    use std::collections::HashMap;
+
+    use crate::parser::Function;
+    use crate::util::operation::{Operand, OperationData, OperationType};
+    use crate::util::type_check::{ErrorTypes, TypeCheckError, Types};
+
+    pub fn create_global_type_check() -> Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError> {
+        Box::new(|data, _, stack, _| {
+            if let OperationType::Global = data.typ {
+                match data.operand.clone() {
+                    Some(Operand::LoadVar(_, typ)) => {
+                        stack.push(typ);
+                        ErrorTypes::None.into()
+                    }
+                    Some(Operand::StoreVar(_, typ)) => {
+                        if stack.len() == 0 {
+                            ErrorTypes::TooFewElements.into_with_ctx(vec![typ], vec![])
+                        } else if stack.last().unwrap() != &typ {
+                            ErrorTypes::InvalidTypes.into_with_ctx(vec![typ], stack.clone())
+                        } else {
+                            stack.pop();
+                            ErrorTypes::None.into()
+                        }
+                    }
+                    _ => {
+                        ErrorTypes::ClosureError.into()
+                    }
+                }
+            } else {
+                ErrorTypes::InvalidTypes.into()
+            }
+        })
+    }
+}
+
+pub mod runtime {
+    use crate::{compiler_error_str, VM};
+    use crate::util::operation::{Operand, OperationData, OperationType};
+    use crate::util::runtime_error;
+
+    pub fn create_global_op() -> Box<dyn Fn(&OperationData, &mut VM)> {
+        Box::new(|data, vm| {
+            let info = &data.data;
+            if let OperationType::Global = data.typ {
+                match data.operand.clone() {
+                    Some(Operand::LoadVar(name, _)) => {
+                        let value = vm.globals_mut().get(&name).cloned();
+                        if let Some(value) = value {
+                            vm.stack_mut().push(value);
+                        } else {
+                            runtime_error(format!("The variable {} does not exist", name), info);
+                        }
+                    }
+                    Some(Operand::StoreVar(name, _)) => {
+                        let value = vm.stack_mut().pop().unwrap();
+                        vm.globals_mut().insert(name, value);
+                    }
+                    _ => {
+                        compiler_error_str("Could not create closure for operation", info);
+                    }
+                }
+            } else {
+                compiler_error_str("Could not create closure for operation", info);
+            }
+        })
+    }
+}
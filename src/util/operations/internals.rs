@@ -4,7 +4,162 @@ pub mod typecheck {
     use crate::parser::Function;
     use crate::util::internals::Internal;
     use crate::util::operation::{Operand, OperationData};
-    use crate::util::type_check::{ErrorTypes, TypeCheckError, Types};
+    use crate::util::type_check::{apply_contract, copies_descriptor, ErrorTypes, TypeCheckError, Types};
+
+    fn generic(name: &str) -> Types {
+        Types::Generic(name.to_string())
+    }
+
+    /// Contracts of the internals which work on any type, expressed through type variables
+    pub fn generic_contract(internal: &Internal) -> Option<(Vec<Types>, Vec<Types>)> {
+        let (a, b, c, d) = (generic("a"), generic("b"), generic("c"), generic("d"));
+        match internal {
+            Internal::Over => Some((vec![a.clone(), b.clone()], vec![a.clone(), b, a])),
+            Internal::Rot => Some((vec![a.clone(), b.clone(), c.clone()], vec![b, c, a])),
+            Internal::RotBack => Some((vec![a.clone(), b.clone(), c.clone()], vec![c, a, b])),
+            Internal::Nip => Some((vec![a, b.clone()], vec![b])),
+            Internal::Tuck => Some((vec![a.clone(), b.clone()], vec![b.clone(), a, b])),
+            Internal::TwoDup => Some((vec![a.clone(), b.clone()], vec![a.clone(), b.clone(), a, b])),
+            Internal::TwoDrop => Some((vec![a, b], vec![])),
+            Internal::TwoSwap => Some((vec![a.clone(), b.clone(), c.clone(), d.clone()], vec![c, d, a, b])),
+            Internal::Pick(depth) => {
+                let inp = (0..=*depth).map(|index| generic(&format!("x{}", index))).collect::<Vec<_>>();
+                let mut outp = inp.clone();
+                outp.push(inp.first().unwrap().clone());
+                Some((inp, outp))
+            }
+            Internal::Roll(depth) => {
+                let inp = (0..=*depth).map(|index| generic(&format!("x{}", index))).collect::<Vec<_>>();
+                let mut outp = inp.clone();
+                outp.rotate_left(1);
+                Some((inp, outp))
+            }
+            Internal::Print | Internal::PrintLn => Some((vec![generic("a")], vec![])),
+            Internal::ToString => Some((vec![generic("a")], vec![Types::String])),
+            Internal::Swap => Some((vec![generic("a"), generic("b")], vec![generic("b"), generic("a")])),
+            Internal::Drop => Some((vec![generic("a")], vec![])),
+            Internal::Dup => Some((vec![generic("a")], vec![generic("a"), generic("a")])),
+            _ => None,
+        }
+    }
+
+    pub fn fs_contract(internal: &Internal) -> Option<(Vec<Types>, Vec<Types>)> {
+        match internal {
+            Internal::FsExists | Internal::FsIsDir | Internal::FsMkdir | Internal::FsRemove => Some((vec![Types::String], vec![Types::Bool])),
+            Internal::FsRename | Internal::FsCopy => Some((vec![Types::String, Types::String], vec![Types::Bool])),
+            Internal::FsListDir => Some((vec![Types::String], vec![Types::Map(Box::new(Types::Int), Box::new(Types::String)), Types::Bool])),
+            Internal::FsSize | Internal::FsMtime => Some((vec![Types::String], vec![Types::Int, Types::Bool])),
+            _ => None,
+        }
+    }
+
+    pub fn env_contract(internal: &Internal) -> Option<(Vec<Types>, Vec<Types>)> {
+        match internal {
+            Internal::EnvArgc => Some((vec![], vec![Types::Int])),
+            Internal::EnvArg => Some((vec![Types::Int], vec![Types::String, Types::Bool])),
+            Internal::EnvGet => Some((vec![Types::String], vec![Types::String, Types::Bool])),
+            Internal::EnvSet => Some((vec![Types::String, Types::String], vec![])),
+            _ => None,
+        }
+    }
+
+    fn map_typecheck(internal: &Internal, stack: &mut Vec<Types>) -> TypeCheckError {
+        let tmp_stack = stack.clone();
+        let any_map = Types::Map(Box::new(Types::Any), Box::new(Types::Any));
+
+        let above = match internal {
+            Internal::MapSet => 2,
+            Internal::MapGet | Internal::MapHas | Internal::MapRemove | Internal::MapForEach => 1,
+            _ => 0,
+        };
+
+        if stack.len() < above + 1 {
+            return ErrorTypes::TooFewElements.into_with_ctx(vec![any_map], tmp_stack);
+        }
+
+        let (key, value) = if let Types::Map(key, value) = stack.get(stack.len() - above - 1).unwrap() {
+            (*key.clone(), *value.clone())
+        } else {
+            return ErrorTypes::InvalidTypes.into_with_ctx(vec![any_map], tmp_stack);
+        };
+
+        let map = Types::Map(Box::new(key.clone()), Box::new(value.clone()));
+
+        let wanted = match internal {
+            Internal::MapSet => vec![map.clone(), key.clone(), value.clone()],
+            Internal::MapGet | Internal::MapHas | Internal::MapRemove => vec![map.clone(), key.clone()],
+            Internal::MapForEach => vec![map.clone(), Types::FunctionPointer(vec![key.clone(), value.clone()], vec![])],
+            _ => vec![map.clone()],
+        };
+
+        if stack[stack.len() - wanted.len()..] != wanted[..] {
+            return ErrorTypes::InvalidTypes.into_with_ctx(wanted, tmp_stack);
+        }
+
+        for _ in 0..wanted.len() {
+            stack.pop();
+        }
+
+        match internal {
+            Internal::MapGet => {
+                stack.push(map);
+                stack.push(value);
+                stack.push(Types::Bool);
+            }
+            Internal::MapHas => {
+                stack.push(map);
+                stack.push(Types::Bool);
+            }
+            Internal::MapKeys => {
+                stack.push(map);
+                stack.push(Types::Map(Box::new(Types::Int), Box::new(key)));
+            }
+            Internal::MapSize => {
+                stack.push(map);
+                stack.push(Types::Int);
+            }
+            _ => {
+                stack.push(map);
+            }
+        }
+
+        ErrorTypes::None.into()
+    }
+
+    /// Registers are tracked outside of the type stack, see Operation::type_check_with_frame
+    pub fn register_typecheck(internal: &Internal, stack: &mut Vec<Types>, registers: &mut Vec<Option<Types>>) -> TypeCheckError {
+        let index = internal.register().unwrap();
+        let name = (b'a' + index as u8) as char;
+
+        match internal {
+            Internal::RegisterStore(_) => {
+                if let Some(typ) = stack.pop() {
+                    registers[index] = Some(typ);
+                    ErrorTypes::None.into()
+                } else {
+                    ErrorTypes::TooFewElements.into_with_ctx(vec![generic("a")], vec![])
+                }
+            }
+            _ => {
+                let typ = if let Internal::RegisterLoad(_) = internal {
+                    registers[index].take()
+                } else {
+                    registers[index].clone()
+                };
+
+                if let (Internal::RegisterPeek(_), Some(true)) = (internal, typ.as_ref().map(|typ| typ.contains_descriptor())) {
+                    return ErrorTypes::Raw(format!("Register {} contains a descriptor which can not be copied. Use {}> instead", name, name)).into();
+                }
+
+                if let Some(typ) = typ {
+                    stack.push(typ);
+                    ErrorTypes::None.into()
+                } else {
+                    ErrorTypes::Raw(format!("Register {} is empty at that point", name)).into()
+                }
+            }
+        }
+    }
 
     pub fn get_internal_typecheck() -> Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError> {
         Box::new(move |data, fncs, stack, compile_time| {
@@ -15,41 +170,19 @@ pub mod typecheck {
                     Internal::NoOp | Internal::DbgStack => {
                         ErrorTypes::None.into()
                     }
-                    Internal::Print | Internal::PrintLn => {
-                        if stack.len() == 0 {
-                            ErrorTypes::TooFewElements.into_with_ctx(vec![Types::Any, Types::Any], tmp_stack)
+                    Internal::Print | Internal::PrintLn | Internal::ToString | Internal::Swap | Internal::Drop | Internal::Dup
+                    | Internal::Over | Internal::Rot | Internal::RotBack | Internal::Nip | Internal::Tuck
+                    | Internal::TwoDup | Internal::TwoDrop | Internal::TwoSwap | Internal::Pick(_) | Internal::Roll(_) => {
+                        let (inp, outp) = generic_contract(internal).unwrap();
+                        if copies_descriptor(&inp, &outp, stack) {
+                            ErrorTypes::Raw("Descriptors can not be copied. Close them with the close action instead".to_string()).into()
                         } else {
-                            stack.pop();
-                            ErrorTypes::None.into()
+                            apply_contract(&inp, &outp, stack)
                         }
                     }
-                    Internal::Swap => {
-                        if stack.len() < 2 {
-                            ErrorTypes::TooFewElements.into_with_ctx(vec![Types::Any, Types::Any], tmp_stack)
-                        } else {
-                            let a = stack.pop().unwrap();
-                            let b = stack.pop().unwrap();
-                            stack.push(a);
-                            stack.push(b);
-                            ErrorTypes::None.into()
-                        }
-                    }
-                    Internal::Drop => {
-                        if stack.len() == 0 {
-                            ErrorTypes::TooFewElements.into_with_ctx(vec![Types::Any], tmp_stack)
-                        } else {
-                            stack.pop();
-                            ErrorTypes::None.into()
-                        }
-                    }
-                    Internal::Dup => {
-                        if stack.len() == 0 {
-                            ErrorTypes::TooFewElements.into_with_ctx(vec![Types::Any], tmp_stack)
-                        } else {
-                            let last = stack.last().unwrap().clone();
-                            stack.push(last);
-                            ErrorTypes::None.into()
-                        }
+                    Internal::Depth => {
+                        stack.push(Types::Int);
+                        ErrorTypes::None.into()
                     }
                     Internal::RevStack => {
                         stack.reverse();
@@ -60,6 +193,9 @@ pub mod typecheck {
                         ErrorTypes::None.into()
                     }
                     Internal::DupStack => {
+                        if stack.iter().any(|typ| typ.contains_descriptor()) {
+                            return ErrorTypes::Raw("Descriptors can not be copied. Close them with the close action instead".to_string()).into();
+                        }
                         stack.extend(stack.clone());
                         ErrorTypes::None.into()
                     }
@@ -205,14 +341,20 @@ pub mod typecheck {
                             }
                         }
                     }
-                    Internal::ToString => {
-                        if stack.len() == 0 {
-                            ErrorTypes::TooFewElements.into_with_ctx(vec![Types::Any], tmp_stack)
-                        } else {
-                            let _ = stack.pop().unwrap();
-                            stack.push(Types::String);
-                            ErrorTypes::None.into()
-                        }
+                    Internal::MapSet | Internal::MapGet | Internal::MapHas | Internal::MapRemove | Internal::MapKeys | Internal::MapSize | Internal::MapForEach => {
+                        map_typecheck(internal, stack)
+                    }
+                    Internal::FsExists | Internal::FsIsDir | Internal::FsMkdir | Internal::FsRemove | Internal::FsRename
+                    | Internal::FsCopy | Internal::FsListDir | Internal::FsSize | Internal::FsMtime => {
+                        let (inp, outp) = fs_contract(internal).unwrap();
+                        apply_contract(&inp, &outp, stack)
+                    }
+                    Internal::EnvArgc | Internal::EnvArg | Internal::EnvGet | Internal::EnvSet => {
+                        let (inp, outp) = env_contract(internal).unwrap();
+                        apply_contract(&inp, &outp, stack)
+                    }
+                    Internal::RegisterStore(_) | Internal::RegisterLoad(_) | Internal::RegisterPeek(_) => {
+                        ErrorTypes::ClosureError.into_txt("Registers can only be type checked with the register types of the function")
                     }
                 }
             } else {
@@ -223,15 +365,21 @@ pub mod typecheck {
 }
 
 pub mod runtime {
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
+    use std::fs;
     use std::io::{stdout, Write};
+    use std::path::Path;
+    use std::rc::Rc;
+    use std::time::UNIX_EPOCH;
 
     use crate::{Position, VM};
     use crate::util::internals::Internal;
     use crate::util::operation::{Operand, OperationData, OperationDataInfo};
     use crate::util::operations::DescriptorAction;
-    use crate::util::register_type::RegisterType;
-    use crate::util::runtime_error_str;
+    use crate::util::operations::internals::typecheck::generic_contract;
+    use crate::util::register_type::{MapKey, RegisterType};
+    use crate::util::{runtime_error, runtime_error_str};
+    use crate::util::type_check::Types;
 
     fn noop(internal: Internal, stack: &mut Vec<RegisterType>, info: &OperationDataInfo) {}
 
@@ -259,11 +407,11 @@ pub mod runtime {
         stack.push(b);
     }
 
-    fn drop(internal: Internal, stack: &mut Vec<RegisterType>, info: &OperationDataInfo) {
-        if let RegisterType::Descriptor(descr) = stack.pop().unwrap() {
-            let mut locked = descr.lock();
-            let locked = locked.as_mut().unwrap();
-            locked.action(DescriptorAction::Close, stack, &info);
+    fn drop(internal: Internal, vm: &mut VM, info: &OperationDataInfo) {
+        if let RegisterType::Descriptor(descr) = vm.stack_mut().pop().unwrap() {
+            // Dropping closes the descriptor, but drop has no outputs, so the result of close is discarded
+            descr.lock().as_mut().unwrap().action(DescriptorAction::Close, &mut vec![], &info);
+            vm.descriptors_mut().retain(|other| !Rc::ptr_eq(other, &descr));
         }
     }
 
@@ -273,7 +421,23 @@ pub mod runtime {
         stack.push(top);
     }
 
-    fn rev_stack(internal: Internal, stack: &mut Vec<RegisterType>, position: Position) {
+    /// Rearranges the stack as described by the generic contract of the internal
+    fn permute(internal: Internal, stack: &mut Vec<RegisterType>, info: &OperationDataInfo) {
+        let (inp, outp) = generic_contract(&internal).unwrap();
+        let values = stack.split_off(stack.len() - inp.len());
+        let values = inp.into_iter().zip(values).collect::<Vec<_>>();
+
+        for typ in outp {
+            let (_, value) = values.iter().find(|(name, _)| *name == typ).unwrap();
+            stack.push(value.clone());
+        }
+    }
+
+    fn depth(internal: Internal, stack: &mut Vec<RegisterType>, info: &OperationDataInfo) {
+        stack.push(RegisterType::Int(stack.len() as i32));
+    }
+
+    fn rev_stack(internal: Internal, stack: &mut Vec<RegisterType>, info: &OperationDataInfo) {
         stack.reverse();
     }
 
@@ -496,6 +660,199 @@ pub mod runtime {
         }
     }
 
+    fn maps(internal: Internal, vm: &mut VM, info: &OperationDataInfo) {
+        if internal == Internal::MapForEach {
+            let fnc = vm.stack_mut().pop().unwrap();
+            let map = vm.stack_mut().pop().unwrap();
+
+            if let (RegisterType::Function(name, ..), RegisterType::Map(key_typ, value_typ, entries)) = (fnc, map.clone()) {
+                if !vm.ops().contains_key(&name) {
+                    runtime_error(format!("Function: {} does not exist", name), info);
+                }
+
+                let fnc = vm.ops().get(&name).unwrap().clone();
+                if fnc.get_contract() != (vec![key_typ.clone(), value_typ.clone()], vec![]) {
+                    runtime_error_str("Typecheck for map iteration failed", info);
+                }
+
+                // The type check already left the map type on the type stack.
+                // The map stays below the entries, so both stacks match while the callback runs
+                vm.stack_mut().push(map);
+
+                for (key, value) in entries {
+                    vm.stack_mut().push(key.to_register());
+                    vm.stack_mut().push(value);
+                    vm.type_stack_mut().push(key_typ.clone());
+                    vm.type_stack_mut().push(value_typ.clone());
+                    vm.execute_fn(&fnc);
+                }
+            }
+            return;
+        }
+
+        let stack = vm.stack_mut();
+        match internal {
+            Internal::MapSet => {
+                let value = stack.pop().unwrap();
+                let key = MapKey::from_register(stack.pop().unwrap(), info);
+                if let RegisterType::Map(key_typ, value_typ, mut entries) = stack.pop().unwrap() {
+                    entries.insert(key, value);
+                    stack.push(RegisterType::Map(key_typ, value_typ, entries));
+                }
+            }
+            Internal::MapRemove => {
+                let key = MapKey::from_register(stack.pop().unwrap(), info);
+                if let RegisterType::Map(key_typ, value_typ, mut entries) = stack.pop().unwrap() {
+                    entries.remove(&key);
+                    stack.push(RegisterType::Map(key_typ, value_typ, entries));
+                }
+            }
+            Internal::MapGet | Internal::MapHas => {
+                let key = MapKey::from_register(stack.pop().unwrap(), info);
+                let map = stack.pop().unwrap();
+                if let RegisterType::Map(_, value_typ, entries) = &map {
+                    let value = entries.get(&key).cloned();
+                    let found = value.is_some();
+                    let value = value.unwrap_or_else(|| RegisterType::default_for(value_typ));
+                    stack.push(map.clone());
+                    if internal == Internal::MapGet {
+                        stack.push(value);
+                    }
+                    stack.push(RegisterType::Bool(found));
+                }
+            }
+            Internal::MapKeys => {
+                let map = stack.pop().unwrap();
+                if let RegisterType::Map(key_typ, _, entries) = &map {
+                    let keys = entries.keys().enumerate().map(|(index, key)| {
+                        (MapKey::Int(index as i32), key.to_register())
+                    }).collect::<BTreeMap<_, _>>();
+                    let keys = RegisterType::Map(Types::Int, key_typ.clone(), keys);
+                    stack.push(map.clone());
+                    stack.push(keys);
+                }
+            }
+            Internal::MapSize => {
+                let map = stack.pop().unwrap();
+                if let RegisterType::Map(_, _, entries) = &map {
+                    let size = entries.len() as i32;
+                    stack.push(map.clone());
+                    stack.push(RegisterType::Int(size));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn fs(internal: Internal, stack: &mut Vec<RegisterType>, info: &OperationDataInfo) {
+        let target = if let Internal::FsRename | Internal::FsCopy = internal {
+            stack.pop().unwrap().to_string(info)
+        } else {
+            None
+        };
+
+        let path = stack.pop().unwrap().to_string(info).unwrap();
+        let path = Path::new(&path);
+
+        match internal {
+            Internal::FsExists => stack.push(RegisterType::Bool(path.exists())),
+            Internal::FsIsDir => stack.push(RegisterType::Bool(path.is_dir())),
+            Internal::FsMkdir => stack.push(RegisterType::Bool(fs::create_dir_all(path).is_ok())),
+            Internal::FsRemove => {
+                let removed = if path.is_dir() { fs::remove_dir_all(path) } else { fs::remove_file(path) };
+                stack.push(RegisterType::Bool(removed.is_ok()))
+            }
+            Internal::FsRename => stack.push(RegisterType::Bool(fs::rename(path, target.unwrap()).is_ok())),
+            Internal::FsCopy => stack.push(RegisterType::Bool(fs::copy(path, target.unwrap()).is_ok())),
+            Internal::FsListDir => {
+                let names = fs::read_dir(path).map(|entries| {
+                    let mut names = entries.filter_map(|entry| entry.ok())
+                        .map(|entry| entry.file_name().to_string_lossy().to_string())
+                        .collect::<Vec<_>>();
+                    names.sort();
+                    names
+                });
+
+                let found = names.is_ok();
+                let entries = names.unwrap_or_default().into_iter().enumerate().map(|(index, name)| {
+                    (MapKey::Int(index as i32), RegisterType::String(name))
+                }).collect::<BTreeMap<_, _>>();
+
+                stack.push(RegisterType::Map(Types::Int, Types::String, entries));
+                stack.push(RegisterType::Bool(found));
+            }
+            Internal::FsSize | Internal::FsMtime => {
+                // Values which do not fit into an int are reported as a failure instead of being truncated
+                let value = fs::metadata(path).ok().and_then(|meta| {
+                    if internal == Internal::FsSize {
+                        i32::try_from(meta.len()).ok()
+                    } else {
+                        meta.modified().ok()
+                            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                            .and_then(|time| i32::try_from(time.as_secs()).ok())
+                    }
+                });
+
+                stack.push(RegisterType::Int(value.unwrap_or(0)));
+                stack.push(RegisterType::Bool(value.is_some()));
+            }
+            _ => {}
+        }
+    }
+
+    fn env(internal: Internal, vm: &mut VM, info: &OperationDataInfo) {
+        match internal {
+            Internal::EnvArgc => {
+                let argc = vm.args().len() as i32;
+                vm.stack_mut().push(RegisterType::Int(argc));
+            }
+            Internal::EnvArg => {
+                if let RegisterType::Int(index) = vm.stack_mut().pop().unwrap() {
+                    let arg = usize::try_from(index).ok().and_then(|index| vm.args().get(index).cloned());
+                    let found = arg.is_some();
+                    vm.stack_mut().push(RegisterType::String(arg.unwrap_or_default()));
+                    vm.stack_mut().push(RegisterType::Bool(found));
+                }
+            }
+            Internal::EnvGet => {
+                let name = vm.stack_mut().pop().unwrap().to_string(info).unwrap();
+                let value = std::env::var(name).ok();
+                let found = value.is_some();
+                vm.stack_mut().push(RegisterType::String(value.unwrap_or_default()));
+                vm.stack_mut().push(RegisterType::Bool(found));
+            }
+            Internal::EnvSet => {
+                let value = vm.stack_mut().pop().unwrap().to_string(info).unwrap();
+                let name = vm.stack_mut().pop().unwrap().to_string(info).unwrap();
+
+                if name.is_empty() || name.contains('=') || name.contains('\0') || value.contains('\0') {
+                    runtime_error(format!("Invalid environment variable {}", name), info);
+                }
+
+                std::env::set_var(name, value);
+            }
+            _ => {}
+        }
+    }
+
+    fn registers(internal: Internal, vm: &mut VM, info: &OperationDataInfo) {
+        let index = internal.register().unwrap();
+        match internal {
+            Internal::RegisterStore(_) => {
+                let top = vm.stack_mut().pop().unwrap();
+                vm.registers_mut()[index] = top;
+            }
+            Internal::RegisterLoad(_) => {
+                let value = std::mem::replace(&mut vm.registers_mut()[index], RegisterType::Empty);
+                vm.stack_mut().push(value);
+            }
+            Internal::RegisterPeek(_) => {
+                let value = vm.registers_mut()[index].clone();
+                vm.stack_mut().push(value);
+            }
+            _ => {}
+        }
+    }
 
     pub fn get_internal_executor() -> Box<dyn Fn(&OperationData, &mut VM)> {
         Box::new(move |op, vm| {
@@ -506,15 +863,23 @@ pub mod runtime {
                     Internal::NoOp => noop(internal, vm.stack_mut(), info),
                     Internal::Print | Internal::PrintLn => print(internal, vm.stack_mut(), info),
                     Internal::Swap => swap(internal, vm.stack_mut(), info),
-                    Internal::Drop => drop(internal, vm.stack_mut(), info),
+                    Internal::Drop => drop(internal, vm, info),
                     Internal::Dup => dup(internal, vm.stack_mut(), info),
-                    Internal::RevStack => dup_stack(internal, vm.stack_mut(), info),
+                    Internal::Over | Internal::Rot | Internal::RotBack | Internal::Nip | Internal::Tuck
+                    | Internal::TwoDup | Internal::TwoDrop | Internal::TwoSwap | Internal::Pick(_) | Internal::Roll(_) => permute(internal, vm.stack_mut(), info),
+                    Internal::Depth => depth(internal, vm.stack_mut(), info),
+                    Internal::RevStack => rev_stack(internal, vm.stack_mut(), info),
                     Internal::DropStack => drop_stack(internal, vm.stack_mut(), info),
                     Internal::DupStack => dup_stack(internal, vm.stack_mut(), info),
                     Internal::DbgStack => dbg_stack(internal, vm.stack_mut(), info),
                     Internal::Plus | Internal::Minus | Internal::Mult | Internal::Div | Internal::Modulo | Internal::Squared | Internal::Cubed => math(internal, vm.stack_mut(), info),
                     Internal::Not | Internal::NotPeek | Internal::Equals | Internal::Larger | Internal::LargerEq | Internal::Smaller | Internal::SmallerEq => bool_ops(internal, vm.stack_mut(), info),
                     Internal::ReflectionRemoveStr | Internal::ReflectionRemoveStrDrop | Internal::ReflectionPush | Internal::ReflectionClear => reflection(internal, vm.stack_mut(), info),
+                    Internal::MapSet | Internal::MapGet | Internal::MapHas | Internal::MapRemove | Internal::MapKeys | Internal::MapSize | Internal::MapForEach => maps(internal, vm, info),
+                    Internal::FsExists | Internal::FsIsDir | Internal::FsMkdir | Internal::FsRemove | Internal::FsRename
+                    | Internal::FsCopy | Internal::FsListDir | Internal::FsSize | Internal::FsMtime => fs(internal, vm.stack_mut(), info),
+                    Internal::EnvArgc | Internal::EnvArg | Internal::EnvGet | Internal::EnvSet => env(internal, vm, info),
+                    Internal::RegisterStore(_) | Internal::RegisterLoad(_) | Internal::RegisterPeek(_) => registers(internal, vm, info),
                     _ => {
                         println!("Internal: {:?} not implemented yet", internal)
                     }
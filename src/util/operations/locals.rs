@@ -0,0 +1,97 @@
+pub mod typecheck {
+    use std::collections::HashMap;
+
+    use crate::parser::Function;
+    use crate::util::operation::{Operand, OperationData, OperationType};
+    use crate::util::type_check::{ErrorTypes, TypeCheckError, Types};
+
+    /// Locals are tracked outside of the type stack, see Operation::type_check_with_frame.
+    /// Locals without a declared type take the type found on the stack
+    pub fn local_typecheck(operand: Option<&Operand>, stack: &mut Vec<Types>, locals: &mut Vec<Types>) -> TypeCheckError {
+        match operand {
+            Some(Operand::Bind(bound)) => {
+                let wanted = bound.iter().enumerate().map(|(index, (_, typ))| {
+                    typ.clone().unwrap_or_else(|| Types::Generic(format!("l{}", index)))
+                }).collect::<Vec<_>>();
+
+                if stack.len() < bound.len() {
+                    return ErrorTypes::TooFewElements.into_with_ctx(wanted, stack.clone());
+                }
+
+                let got = stack.split_off(stack.len() - bound.len());
+
+                for ((name, typ), got_typ) in bound.iter().zip(&got) {
+                    if got_typ.contains_descriptor() {
+                        return ErrorTypes::Raw(format!("The local {} can not hold a descriptor, because every use copies it", name)).into();
+                    }
+
+                    if typ.as_ref().map_or(false, |typ| typ != got_typ) {
+                        return ErrorTypes::InvalidTypes.into_with_ctx(wanted, got);
+                    }
+                }
+
+                locals.extend(got);
+                ErrorTypes::None.into()
+            }
+            Some(Operand::PushLocal(_, offset)) => {
+                if let Some(typ) = locals.len().checked_sub(offset + 1).and_then(|index| locals.get(index)) {
+                    stack.push(typ.clone());
+                    ErrorTypes::None.into()
+                } else {
+                    ErrorTypes::ClosureError.into_txt("The local is not bound at that point")
+                }
+            }
+            Some(Operand::Unbind(amount)) => {
+                locals.truncate(locals.len().saturating_sub(*amount));
+                ErrorTypes::None.into()
+            }
+            _ => {
+                ErrorTypes::ClosureError.into()
+            }
+        }
+    }
+
+    pub fn create_local_type_check() -> Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError> {
+        Box::new(|data, _, _, _| {
+            if let OperationType::Local = data.typ {
+                ErrorTypes::ClosureError.into_txt("Locals can only be type checked with the local types of the function")
+            } else {
+                ErrorTypes::InvalidTypes.into()
+            }
+        })
+    }
+}
+
+pub mod runtime {
+    use crate::{compiler_error_str, VM};
+    use crate::util::operation::{Operand, OperationData, OperationType};
+
+    pub fn create_local_op() -> Box<dyn Fn(&OperationData, &mut VM)> {
+        Box::new(|data, vm| {
+            let info = &data.data;
+            if let OperationType::Local = data.typ {
+                match data.operand.clone() {
+                    Some(Operand::Bind(locals)) => {
+                        let stack = vm.stack_mut();
+                        let values = stack.split_off(stack.len() - locals.len());
+                        vm.locals_mut().extend(values);
+                    }
+                    Some(Operand::PushLocal(_, offset)) => {
+                        let locals = vm.locals_mut();
+                        let value = locals.get(locals.len() - 1 - offset).unwrap().clone();
+                        vm.stack_mut().push(value);
+                    }
+                    Some(Operand::Unbind(amount)) => {
+                        let locals = vm.locals_mut();
+                        locals.truncate(locals.len() - amount);
+                    }
+                    _ => {
+                        compiler_error_str("Could not create closure for operation", info);
+                    }
+                }
+            } else {
+                compiler_error_str("Could not create closure for operation", info);
+            }
+        })
+    }
+}
@@ -4,14 +4,26 @@ use std::sync::Arc;
 
 pub use descriptors::Descriptor;
 pub use descriptors::DescriptorAction;
+pub use descriptors::DescriptorConstructor;
+pub use descriptors::action_contract;
+pub use descriptors::descriptor_exists;
+pub use descriptors::register_descriptor;
+pub use internals::typecheck::register_typecheck;
+pub use locals::typecheck::local_typecheck;
 use descriptors::execute_fn as descriptors_runtime;
 use descriptors::type_check_fn as descriptors_typecheck;
 use functions::runtime as calling_runtime;
 use functions::typecheck as calling_typecheck;
+use globals::runtime as globals_runtime;
+use globals::typecheck as globals_typecheck;
 use internals::runtime as internals_runtime;
 use internals::typecheck as internals_typecheck;
+use locals::runtime as locals_runtime;
+use locals::typecheck as locals_typecheck;
 use simple::runtime as simple_runtime;
 use simple::typecheck as simple_typecheck;
+use structs::runtime as structs_runtime;
+use structs::typecheck as structs_typecheck;
 
 use crate::parser::Function;
 use crate::util::operation::OperationData;
@@ -22,6 +34,9 @@ mod simple;
 mod internals;
 mod functions;
 mod descriptors;
+mod structs;
+mod locals;
+mod globals;
 
 
 pub const SIMPLE_TYPECHECK: SyncLazy<Arc<Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError>>> = SyncLazy::new(|| {
@@ -54,4 +69,28 @@ pub const DESCRIPTOR_TYPECHECK: SyncLazy<Arc<Box<dyn Fn(&OperationData, &HashMap
 
 pub const DESCRIPTOR_RUNTIME: SyncLazy<Arc<Box<dyn Fn(&OperationData, &mut VM)>>> = SyncLazy::new(|| {
     Arc::new(descriptors::execute_fn())
+});
+
+pub const STRUCT_TYPECHECK: SyncLazy<Arc<Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError>>> = SyncLazy::new(|| {
+    Arc::new(structs_typecheck::create_struct_type_check())
+});
+
+pub const STRUCT_RUNTIME: SyncLazy<Arc<Box<dyn Fn(&OperationData, &mut VM)>>> = SyncLazy::new(|| {
+    Arc::new(structs_runtime::create_struct_op())
+});
+
+pub const LOCAL_TYPECHECK: SyncLazy<Arc<Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError>>> = SyncLazy::new(|| {
+    Arc::new(locals_typecheck::create_local_type_check())
+});
+
+pub const LOCAL_RUNTIME: SyncLazy<Arc<Box<dyn Fn(&OperationData, &mut VM)>>> = SyncLazy::new(|| {
+    Arc::new(locals_runtime::create_local_op())
+});
+
+pub const GLOBAL_TYPECHECK: SyncLazy<Arc<Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError>>> = SyncLazy::new(|| {
+    Arc::new(globals_typecheck::create_global_type_check())
+});
+
+pub const GLOBAL_RUNTIME: SyncLazy<Arc<Box<dyn Fn(&OperationData, &mut VM)>>> = SyncLazy::new(|| {
+    Arc::new(globals_runtime::create_global_op())
 });
\ No newline at end of file
@@ -7,7 +7,7 @@ pub mod typecheck {
 
     pub fn create_push_type_check() -> Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError> {
         Box::new(|data, _, stack, _| {
-            if let OperationType::Push = data.typ {
+            if let OperationType::Push | OperationType::PushFunction = data.typ {
                 if data.operand.is_some() {
                     let value = data.clone().operand.unwrap();
                     let opt = match value {
@@ -20,8 +20,11 @@ pub mod typecheck {
                         Operand::Bool(_) => {
                             Some(Types::Bool)
                         }
-                        Operand::PushFunction(_, _, _) => {
-                            Some(Types::Function)
+                        Operand::PushFunction(_, inp, outp) => {
+                            Some(Types::FunctionPointer(inp, outp))
+                        }
+                        Operand::NewMap(key, value) => {
+                            Some(Types::Map(Box::new(key), Box::new(value)))
                         }
                         _ => {
                             None
@@ -45,32 +48,16 @@ pub mod typecheck {
 
 pub mod runtime {
     use crate::{compiler_error_str, VM};
-    use crate::util::operation::{Operand, OperationData, OperationType};
+    use crate::util::operation::{OperationData, OperationType};
     use crate::util::register_type::RegisterType;
 
     pub fn create_push() -> Box<dyn Fn(&OperationData, &mut VM)> {
         Box::new(|data, vm| {
             let info = &data.data;
-            if let OperationType::Push = data.typ {
+            if let OperationType::Push | OperationType::PushFunction = data.typ {
                 if data.operand.is_some() {
                     let value = data.clone().operand.unwrap();
-                    let opt = match value {
-                        Operand::Int(val) => {
-                            Some(RegisterType::Int(val))
-                        }
-                        Operand::Str(val) => {
-                            Some(RegisterType::String(val))
-                        }
-                        Operand::Bool(val) => {
-                            Some(RegisterType::Bool(val))
-                        }
-                        Operand::PushFunction(name, inp, outp) => {
-                            Some(RegisterType::Function(name, inp, outp))
-                        }
-                        _ => {
-                            None
-                        }
-                    };
+                    let opt = RegisterType::from_operand(value);
                     if opt.is_some() {
                         vm.stack_mut().push(opt.unwrap())
                     }
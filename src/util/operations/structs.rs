@@ -0,0 +1,82 @@
+pub mod typecheck {
+    use std::collections::HashMap;
+
+    use crate::parser::Function;
+    use crate::util::operation::{Operand, OperationData, OperationType};
+    use crate::util::type_check::{ErrorTypes, TypeCheckError, Types};
+
+    pub fn create_struct_type_check() -> Box<dyn Fn(&OperationData, &HashMap<String, Function>, &mut Vec<Types>, bool) -> TypeCheckError> {
+        Box::new(|data, _, stack, _| {
+            if let OperationType::Struct = data.typ {
+                let tmp_stack = stack.clone();
+                let (wanted, push) = match data.operand.clone() {
+                    Some(Operand::StructNew(name, fields)) => {
+                        (fields.into_iter().map(|(_, typ)| typ).collect::<Vec<_>>(), vec![Types::Struct(name)])
+                    }
+                    Some(Operand::StructGet(name, _, typ)) => {
+                        (vec![Types::Struct(name.clone())], vec![Types::Struct(name), typ])
+                    }
+                    Some(Operand::StructSet(name, _, typ)) => {
+                        (vec![Types::Struct(name.clone()), typ], vec![Types::Struct(name)])
+                    }
+                    _ => {
+                        return ErrorTypes::ClosureError.into();
+                    }
+                };
+
+                if stack.len() < wanted.len() {
+                    ErrorTypes::TooFewElements.into_with_ctx(wanted, tmp_stack)
+                } else if stack[stack.len() - wanted.len()..] != wanted[..] {
+                    ErrorTypes::InvalidTypes.into_with_ctx(wanted, tmp_stack)
+                } else {
+                    for _ in 0..wanted.len() {
+                        stack.pop();
+                    }
+                    stack.extend(push);
+                    ErrorTypes::None.into()
+                }
+            } else {
+                ErrorTypes::InvalidTypes.into()
+            }
+        })
+    }
+}
+
+pub mod runtime {
+    use crate::{compiler_error_str, VM};
+    use crate::util::operation::{Operand, OperationData, OperationType};
+    use crate::util::register_type::RegisterType;
+
+    pub fn create_struct_op() -> Box<dyn Fn(&OperationData, &mut VM)> {
+        Box::new(|data, vm| {
+            let info = &data.data;
+            if let OperationType::Struct = data.typ {
+                let stack = vm.stack_mut();
+                match data.operand.clone() {
+                    Some(Operand::StructNew(name, fields)) => {
+                        let values = stack.split_off(stack.len() - fields.len());
+                        let fields = fields.into_iter().map(|(field, _)| field).zip(values).collect();
+                        stack.push(RegisterType::Struct(name, fields));
+                    }
+                    Some(Operand::StructGet(_, index, _)) => {
+                        let top = stack.last().unwrap().clone();
+                        if let RegisterType::Struct(_, fields) = top {
+                            stack.push(fields.get(index).unwrap().1.clone());
+                        }
+                    }
+                    Some(Operand::StructSet(_, index, _)) => {
+                        let value = stack.pop().unwrap();
+                        if let Some(RegisterType::Struct(_, fields)) = stack.last_mut() {
+                            fields.get_mut(index).unwrap().1 = value;
+                        }
+                    }
+                    _ => {
+                        compiler_error_str("Could not create closure for operation", info);
+                    }
+                }
+            } else {
+                compiler_error_str("Could not create closure for operation", info);
+            }
+        })
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::parser::Function;
+use crate::util::compiler_warning_str;
+use crate::util::internals::Internal;
+use crate::util::operation::{Operand, Operation, OperationData, OperationDataInfo, OperationType};
+
+/// Functions with at most this many operations are inlined
+const MAX_INLINE_SIZE: usize = 8;
+
+/// Runs the optimisations enabled by the level on all functions.
+/// Level 0: No optimisations
+/// Level 1: Constant folding, removal of noop and removal of functions unreachable from main
+/// Level 2: Level 1 and inlining of small functions
+pub fn optimize(functions: HashMap<String, Function>, level: u8, roots: Vec<String>) -> HashMap<String, Function> {
+    if level == 0 {
+        return functions;
+    }
+
+    let functions = if level >= 2 {
+        inline_functions(functions)
+    } else {
+        functions
+    };
+
+    let functions = functions.into_iter().map(|(name, function)| {
+        let operations = fold_constants(function.operations);
+        (name, Function { operations, ..function })
+    }).collect();
+
+    remove_unreachable(functions, roots)
+}
+
+fn literal(op: &Operation) -> Option<Operand> {
+    if op.data.typ != OperationType::Push {
+        return None;
+    }
+
+    match &op.data.operand {
+        Some(operand @ (Operand::Int(_) | Operand::Str(_) | Operand::Bool(_))) => Some(operand.clone()),
+        _ => None,
+    }
+}
+
+fn internal(op: &Operation) -> Option<Internal> {
+    if let Some(Operand::Internal(internal)) = &op.data.operand {
+        Some(*internal)
+    } else {
+        None
+    }
+}
+
+/// Evaluates an internal on literals the same way the runtime does.
+/// The top of the stack is the last operand
+fn evaluate(internal: Internal, operands: &[Operand]) -> Option<Operand> {
+    match (internal, operands) {
+        (Internal::Plus, [Operand::Int(bottom), Operand::Int(top)]) => top.checked_add(*bottom).map(Operand::Int),
+        (Internal::Minus, [Operand::Int(bottom), Operand::Int(top)]) => top.checked_sub(*bottom).map(Operand::Int),
+        (Internal::Mult, [Operand::Int(bottom), Operand::Int(top)]) => top.checked_mul(*bottom).map(Operand::Int),
+        (Internal::Div, [Operand::Int(bottom), Operand::Int(top)]) => top.checked_div(*bottom).map(Operand::Int),
+        (Internal::Modulo, [Operand::Int(bottom), Operand::Int(top)]) => top.checked_rem(*bottom).map(Operand::Int),
+        (Internal::Squared, [Operand::Int(top)]) => top.checked_mul(*top).map(Operand::Int),
+        (Internal::Cubed, [Operand::Int(top)]) => top.checked_mul(*top).and_then(|squared| squared.checked_mul(*top)).map(Operand::Int),
+        (Internal::Not, [Operand::Bool(top)]) => Some(Operand::Bool(!top)),
+        (Internal::Equals, [Operand::Int(bottom), Operand::Int(top)]) => Some(Operand::Bool(top == bottom)),
+        (Internal::Equals, [Operand::Str(bottom), Operand::Str(top)]) => Some(Operand::Bool(top == bottom)),
+        (Internal::Equals, [Operand::Bool(bottom), Operand::Bool(top)]) => Some(Operand::Bool(top == bottom)),
+        (Internal::Larger, [Operand::Int(bottom), Operand::Int(top)]) => Some(Operand::Bool(top > bottom)),
+        (Internal::Smaller, [Operand::Int(bottom), Operand::Int(top)]) => Some(Operand::Bool(top < bottom)),
+        (Internal::LargerEq, [Operand::Int(bottom), Operand::Int(top)]) => Some(Operand::Bool(top >= bottom)),
+        (Internal::SmallerEq, [Operand::Int(bottom), Operand::Int(top)]) => Some(Operand::Bool(top <= bottom)),
+        _ => None,
+    }
+}
+
+fn arity(internal: Internal) -> usize {
+    match internal {
+        Internal::Squared | Internal::Cubed | Internal::Not => 1,
+        _ => 2,
+    }
+}
+
+/// Replaces pure operations on literals with their result and removes noop
+fn fold_constants(operations: Vec<(OperationDataInfo, Operation)>) -> Vec<(OperationDataInfo, Operation)> {
+    operations.into_iter().fold(vec![], |mut acc: Vec<(OperationDataInfo, Operation)>, (info, op)| {
+        let internal = internal(&op);
+
+        if internal == Some(Internal::NoOp) {
+            return acc;
+        }
+
+        if let Some(internal) = internal {
+            let arity = arity(internal);
+
+            if acc.len() >= arity {
+                let operands = acc[acc.len() - arity..].iter()
+                    .map(|(_, op)| literal(op))
+                    .collect::<Option<Vec<_>>>();
+
+                if let Some(result) = operands.and_then(|operands| evaluate(internal, &operands)) {
+                    acc.truncate(acc.len() - arity);
+
+                    let data = OperationData { typ: OperationType::Push, data: op.data.data.clone(), operand: Some(result) };
+                    acc.push((info, Operation::from(data)));
+                    return acc;
+                }
+            }
+        }
+
+        acc.push((info, op));
+        acc
+    })
+}
+
+/// Keeps only the functions reachable from the roots (main or the exports of a library) through calls or function pointers
+fn remove_unreachable(functions: HashMap<String, Function>, roots: Vec<String>) -> HashMap<String, Function> {
+    let mut reachable = HashSet::new();
+    let mut queue = roots;
+
+    while let Some(name) = queue.pop() {
+        if !reachable.insert(name.clone()) {
+            continue;
+        }
+
+        if let Some(function) = functions.get(&name) {
+            for (_, op) in &function.operations {
+                match &op.data.operand {
+                    Some(Operand::Call(callee)) | Some(Operand::PushFunction(callee, _, _)) => {
+                        queue.push(callee.clone());
+                    }
+                    Some(Operand::Internal(Internal::ReflectionPush | Internal::ReflectionClear | Internal::ReflectionRemoveStr | Internal::ReflectionRemoveStrDrop)) => {
+                        // Reflection can create a handle to any function
+                        compiler_warning_str("Reflection is used. Unreachable functions are kept", &OperationDataInfo::None);
+                        return functions;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    functions.into_iter().filter(|(name, _)| reachable.contains(name)).collect()
+}
+
+fn callees(function: &Function) -> Vec<String> {
+    function.operations.iter().filter_map(|(_, op)| {
+        if let Some(Operand::Call(callee)) | Some(Operand::PushFunction(callee, _, _)) = &op.data.operand {
+            Some(callee.clone())
+        } else {
+            None
+        }
+    }).collect()
+}
+
+fn is_recursive(name: &str, functions: &HashMap<String, Function>) -> bool {
+    let mut visited = HashSet::new();
+    let mut queue = functions.get(name).map_or(vec![], callees);
+
+    while let Some(callee) = queue.pop() {
+        if callee == name {
+            return true;
+        }
+
+        if visited.insert(callee.clone()) {
+            if let Some(function) = functions.get(&callee) {
+                queue.extend(callees(function));
+            }
+        }
+    }
+
+    false
+}
+
+fn is_inlinable(name: &str, function: &Function, functions: &HashMap<String, Function>) -> bool {
+    // Local registers are restored when the function returns, so it needs its own frame
+    name != "main"
+        && function.operations.len() <= MAX_INLINE_SIZE
+        && function.local_registers.is_empty()
+        && !is_recursive(name, functions)
+}
+
+fn expand(operations: &Vec<(OperationDataInfo, Operation)>, inlinable: &HashMap<String, Function>) -> Vec<(OperationDataInfo, Operation)> {
+    operations.iter().flat_map(|(info, op)| {
+        match &op.data.operand {
+            Some(Operand::Call(callee)) if op.data.typ == OperationType::Call && inlinable.contains_key(callee) => {
+                // The operations keep the position of the callee, so errors still point at its source
+                expand(&inlinable.get(callee).unwrap().operations, inlinable)
+            }
+            _ => vec![(info.clone(), op.clone())]
+        }
+    }).collect()
+}
+
+/// Replaces calls to small non-recursive functions with the operations of the callee
+fn inline_functions(functions: HashMap<String, Function>) -> HashMap<String, Function> {
+    let inlinable = functions.iter()
+        .filter(|(name, function)| is_inlinable(name, function, &functions))
+        .map(|(name, function)| (name.clone(), function.clone()))
+        .collect::<HashMap<_, _>>();
+
+    functions.iter().map(|(name, function)| {
+        let operations = expand(&function.operations, &inlinable);
+        (name.clone(), Function { operations, ..function.clone() })
+    }).collect()
+}
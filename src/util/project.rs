@@ -0,0 +1,301 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::cache::{compiler_version, hash_file};
+use crate::util::compile::ProgramMetadata;
+use crate::util::compiler_error;
+use crate::util::operation::OperationDataInfo;
+
+/// The folder inside a project which holds the build output
+pub const TARGET_DIR: &'static str = "target";
+
+/// The folder inside the target folder which holds the parsed files
+const CACHE_DIR: &'static str = "cache";
+
+/// Package names which are already used by the includes shipped with the compiler
+const RESERVED_PACKAGES: [&'static str; 2] = ["std", "opt"];
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Dependency {
+    /// Path to the package folder, relative to the manifest
+    pub(crate) path: String,
+}
+
+/// The manifest of a stackyy project.
+/// It lives in <name>-meta.scy.yml and extends the program metadata:
+/// ---
+/// name: "ExampleProgram"
+/// version: "1.0"
+/// author: "Flawlesscode"
+/// entry: "ExampleProgram-main.scy"
+/// sources:
+///   - "lib"
+/// dependencies:
+///   logging:
+///     path: "../logging-scy"
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProjectManifest {
+    #[serde(flatten)]
+    pub data: ProgramMetadata,
+    /// The file containing the main function. Defaults to <name>-main.scy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) entry: Option<String>,
+    /// Folders searched for includes which are not found next to the including file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) sources: Vec<String>,
+    /// Other stackyy packages. Their files are included with "@<name>/<file>"
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub(crate) dependencies: HashMap<String, Dependency>,
+}
+
+impl ProjectManifest {
+    pub fn new(data: ProgramMetadata) -> Self {
+        let entry = Some(format!("{}-main.scy", data.name));
+        Self {
+            data,
+            entry,
+            sources: vec![],
+            dependencies: HashMap::new(),
+        }
+    }
+}
+
+pub struct Project {
+    root: PathBuf,
+    manifest_path: PathBuf,
+    manifest: ProjectManifest,
+}
+
+impl Project {
+    /// The folder or parent folder containing a project manifest
+    fn find_root(start: &Path) -> Option<&Path> {
+        start.ancestors().find(|dir| {
+            fs::read_dir(dir).map(|entries| {
+                entries.filter_map(|entry| entry.ok())
+                    .any(|entry| entry.path().to_string_lossy().ends_with("-meta.scy.yml"))
+            }).unwrap_or(false)
+        })
+    }
+
+    /// Searches the folder and all its parents for a project manifest
+    pub fn find(start: &Path) -> Self {
+        let root = Self::find_root(start);
+
+        if let None = root {
+            compiler_error(format!("Could not find a *-meta.scy.yml in {:?} or any parent folder", start), &OperationDataInfo::None);
+        }
+
+        Self::load(root.unwrap())
+    }
+
+    /// Loads a project either from its manifest or from the folder containing a *-meta.scy.yml
+    pub fn load(path: &Path) -> Self {
+        let manifest_path = if path.is_dir() {
+            let manifests = fs::read_dir(path).map(|entries| {
+                entries.filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|entry| entry.to_string_lossy().ends_with("-meta.scy.yml"))
+                    .collect::<Vec<_>>()
+            }).unwrap_or_default();
+
+            if manifests.len() != 1 {
+                compiler_error(format!("Expected exactly one *-meta.scy.yml in {:?}. Found {}", path, manifests.len()), &OperationDataInfo::None);
+            }
+
+            manifests[0].clone()
+        } else {
+            path.to_path_buf()
+        };
+
+        let manifest_path = fs::canonicalize(&manifest_path).unwrap_or_else(|_| {
+            compiler_error(format!("The manifest {:?} could not be found", manifest_path), &OperationDataInfo::None);
+        });
+
+        let content = fs::read_to_string(&manifest_path).unwrap_or_else(|err| {
+            compiler_error(format!("Could not read manifest {:?}: {}", manifest_path, err), &OperationDataInfo::None);
+        });
+
+        let manifest = serde_yaml::from_str::<ProjectManifest>(&content).unwrap_or_else(|err| {
+            compiler_error(format!("The manifest {:?} is invalid: {}", manifest_path, err), &OperationDataInfo::None);
+        });
+
+        Self {
+            root: manifest_path.parent().unwrap().to_path_buf(),
+            manifest_path,
+            manifest,
+        }
+    }
+
+    pub fn root(&self) -> &PathBuf {
+        &self.root
+    }
+
+    pub fn manifest_path(&self) -> &PathBuf {
+        &self.manifest_path
+    }
+
+    pub fn manifest(&self) -> &ProjectManifest {
+        &self.manifest
+    }
+
+    pub fn entry(&self) -> PathBuf {
+        let entry = self.manifest.entry.clone().unwrap_or_else(|| format!("{}-main.scy", self.manifest.data.name));
+        self.root.join(entry)
+    }
+
+    /// The default path of the compiled program: target/<name>.scyc
+    pub fn output(&self) -> PathBuf {
+        self.root.join(TARGET_DIR).join(format!("{}.scyc", self.manifest.data.name))
+    }
+
+    /// The folder holding the parsed files of this project: target/cache
+    pub fn cache_dir(&self) -> PathBuf {
+        self.root.join(TARGET_DIR).join(CACHE_DIR)
+    }
+
+    /// The cache folder of the project containing the file.
+    /// Files outside of a project use the target folder next to them
+    pub fn cache_dir_of(file: &Path) -> PathBuf {
+        let file = fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf());
+        let dir = file.parent().unwrap_or(Path::new("."));
+        Self::find_root(dir).unwrap_or(dir).join(TARGET_DIR).join(CACHE_DIR)
+    }
+
+    /// The folders containing the sources of this project. Defaults to the project root
+    pub fn source_dirs(&self) -> Vec<PathBuf> {
+        if self.manifest.sources.is_empty() {
+            vec![self.root.clone()]
+        } else {
+            self.manifest.sources.iter().map(|source| self.root.join(source)).collect()
+        }
+    }
+
+    /// Resolves all dependencies recursively into a resolver for the include statements
+    pub fn resolver(&self) -> IncludeResolver {
+        let mut packages = HashMap::new();
+        self.collect_packages(&mut packages);
+
+        IncludeResolver {
+            sources: self.source_dirs(),
+            packages: packages.into_iter().map(|(name, (_, dirs))| (name, dirs)).collect(),
+        }
+    }
+
+    fn collect_packages(&self, packages: &mut HashMap<String, (PathBuf, Vec<PathBuf>)>) {
+        for (name, dependency) in &self.manifest.dependencies {
+            if RESERVED_PACKAGES.contains(&name.as_str()) || name.contains("/") {
+                compiler_error(format!("Invalid dependency name {} in {:?}", name, self.manifest_path), &OperationDataInfo::None);
+            }
+
+            let project = Project::load(&self.root.join(&dependency.path));
+
+            if let Some((root, _)) = packages.get(name) {
+                if root != project.root() {
+                    compiler_error(format!("The dependency {} resolves to {:?} and {:?}", name, root, project.root()), &OperationDataInfo::None);
+                }
+                continue;
+            }
+
+            packages.insert(name.clone(), (project.root().clone(), project.source_dirs()));
+            project.collect_packages(packages);
+        }
+    }
+}
+
+/// Records the compiler and the sources a program was compiled from.
+/// It is stored next to the output as <output>.stamp to skip builds of unchanged projects
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct BuildStamp {
+    version: String,
+    options: String,
+    files: Vec<(PathBuf, u64)>,
+}
+
+impl BuildStamp {
+    pub fn new(files: Vec<PathBuf>, options: String) -> Self {
+        Self {
+            version: compiler_version(),
+            options,
+            files: files.into_iter().map(|file| {
+                let hash = hash_file(&file).unwrap_or_default();
+                (file, hash)
+            }).collect(),
+        }
+    }
+
+    fn path(output: &Path) -> PathBuf {
+        let mut path = output.as_os_str().to_owned();
+        path.push(".stamp");
+        PathBuf::from(path)
+    }
+
+    /// Checks if the output exists and none of the recorded sources changed
+    pub fn is_fresh(output: &Path, options: &str) -> bool {
+        let stamp = fs::read_to_string(Self::path(output)).ok()
+            .and_then(|content| serde_yaml::from_str::<BuildStamp>(&content).ok());
+
+        if let Some(stamp) = stamp {
+            let files = stamp.files.iter().map(|(file, _)| file.clone()).collect();
+            output.exists() && stamp == BuildStamp::new(files, options.to_string())
+        } else {
+            false
+        }
+    }
+
+    pub fn write(&self, output: &Path) {
+        let content = serde_yaml::to_string(self).expect("stamp to be serialized");
+        if fs::write(Self::path(output), content).is_err() {
+            compiler_error(format!("Could not write the build stamp for {:?}", output), &OperationDataInfo::None);
+        }
+    }
+}
+
+/// Finds the files named by include statements
+#[derive(Default, Clone)]
+pub struct IncludeResolver {
+    sources: Vec<PathBuf>,
+    packages: HashMap<String, Vec<PathBuf>>,
+}
+
+impl IncludeResolver {
+    /// Hashes the folders includes are searched in, so cached files are not reused after the manifest changed them
+    pub fn fingerprint(&self) -> u64 {
+        let mut packages = self.packages.iter().collect::<Vec<_>>();
+        packages.sort();
+
+        let mut hasher = DefaultHasher::new();
+        self.sources.hash(&mut hasher);
+        packages.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Resolves a relative include. The folder of the including file is searched first
+    pub fn resolve(&self, include: &str, from: &Path) -> Option<PathBuf> {
+        std::iter::once(&from.to_path_buf())
+            .chain(self.sources.iter())
+            .find_map(|dir| fs::canonicalize(dir.join(include)).ok())
+    }
+
+    /// Checks if an include of the form @<package>/<file> names a dependency
+    pub fn is_package(&self, include: &str) -> bool {
+        Self::split_package(include).map_or(false, |(package, _)| self.packages.contains_key(package))
+    }
+
+    /// Resolves an include of the form @<package>/<file> in the folders of the package
+    pub fn resolve_package(&self, include: &str) -> Option<PathBuf> {
+        let (package, file) = Self::split_package(include)?;
+
+        self.packages.get(package)?
+            .iter()
+            .find_map(|dir| fs::canonicalize(dir.join(file)).ok())
+    }
+
+    fn split_package(include: &str) -> Option<(&str, &str)> {
+        include.strip_prefix("@")?.split_once("/")
+    }
+}
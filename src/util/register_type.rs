@@ -1,8 +1,9 @@
+use std::collections::BTreeMap;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
 use crate::Position;
-use crate::util::operation::OperationDataInfo;
+use crate::util::operation::{Operand, OperationDataInfo};
 use crate::util::operations::{Descriptor, DescriptorAction};
 use crate::util::runtime_error_str;
 use crate::util::type_check::Types;
@@ -15,9 +16,36 @@ pub enum RegisterType {
     String(String),
     Bool(bool),
     Descriptor(Rc<Mutex<Box<dyn Descriptor>>>),
+    Map(Types, Types, BTreeMap<MapKey, RegisterType>),
+    Struct(String, Vec<(String, RegisterType)>),
     Empty,
 }
 
+#[derive(Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+pub enum MapKey {
+    Int(i32),
+    String(String),
+}
+
+impl MapKey {
+    pub fn from_register(reg: RegisterType, info: &OperationDataInfo) -> Self {
+        match reg {
+            RegisterType::Int(int) => MapKey::Int(int),
+            RegisterType::String(str) => MapKey::String(str),
+            _ => {
+                runtime_error_str("Only int and str can be used as map keys", info);
+            }
+        }
+    }
+
+    pub fn to_register(&self) -> RegisterType {
+        match self {
+            MapKey::Int(int) => RegisterType::Int(*int),
+            MapKey::String(str) => RegisterType::String(str.clone()),
+        }
+    }
+}
+
 
 impl RegisterType {
     pub fn to_string(&self, info: &OperationDataInfo) -> Option<String> {
@@ -45,12 +73,50 @@ impl RegisterType {
                 let str = tmp_stack.get(0).unwrap();
                 str.to_string(&info)
             }
+            RegisterType::Map(_, _, map) => {
+                let entries = map.iter().map(|(key, value)| {
+                    let key = key.to_register().to_string(info).unwrap();
+                    let value = value.to_string(info).unwrap_or_else(|| "Empty".to_string());
+                    format!("{}: {}", key, value)
+                }).collect::<Vec<_>>();
+                Some(format!("{{{}}}", entries.join(", ")))
+            }
+            RegisterType::Struct(name, fields) => {
+                let fields = fields.iter().map(|(field, value)| {
+                    let value = value.to_string(info).unwrap_or_else(|| "Empty".to_string());
+                    format!("{}: {}", field, value)
+                }).collect::<Vec<_>>();
+                Some(format!("{}{{{}}}", name, fields.join(", ")))
+            }
             RegisterType::Empty => {
                 None
             }
         }
     }
 
+    /// The value of a pushed operand
+    pub fn from_operand(operand: Operand) -> Option<Self> {
+        match operand {
+            Operand::Int(val) => Some(RegisterType::Int(val)),
+            Operand::Str(val) => Some(RegisterType::String(val)),
+            Operand::Bool(val) => Some(RegisterType::Bool(val)),
+            Operand::PushFunction(name, inp, outp) => Some(RegisterType::Function(name, inp, outp)),
+            Operand::NewMap(key, value) => Some(RegisterType::Map(key, value, BTreeMap::new())),
+            _ => None,
+        }
+    }
+
+    /// The value pushed in place of a missing map entry
+    pub fn default_for(typ: &Types) -> Self {
+        match typ {
+            Types::Int => RegisterType::Int(0),
+            Types::String => RegisterType::String(String::new()),
+            Types::Bool => RegisterType::Bool(false),
+            Types::Map(key, value) => RegisterType::Map(*key.clone(), *value.clone(), BTreeMap::new()),
+            _ => RegisterType::Empty,
+        }
+    }
+
     pub fn to_string_stacked(&self, info: &OperationDataInfo, stack: &mut Vec<RegisterType>) {
         let str = self.to_string(&info);
         if let Some(str) = str {
@@ -6,8 +6,8 @@ use serde::{Deserialize, Serialize};
 use crate::util::{compiler_error, compiler_error_str, compiler_warning};
 use crate::util::operation::OperationDataInfo;
 use crate::util::position::Position;
-use crate::util::token::Keyword::{Call, CallIf, End, INCLUDE};
-use crate::util::type_check::Types;
+use crate::util::token::Keyword::{As, Call, CallIf, Const, End, In, INCLUDE, Let, Local, Private, Struct, Var};
+use crate::util::type_check::{split_types, Types};
 
 static KEY_WORD_MAP: SyncLazy<HashMap<String, Keyword>> = SyncLazy::new(|| {
     let mut map = HashMap::new();
@@ -15,6 +15,14 @@ static KEY_WORD_MAP: SyncLazy<HashMap<String, Keyword>> = SyncLazy::new(|| {
     map.insert("end".to_string(), End);
     map.insert("@".to_string(), Call);
     map.insert("@if".to_string(), CallIf);
+    map.insert("struct".to_string(), Struct);
+    map.insert("let".to_string(), Let);
+    map.insert("in".to_string(), In);
+    map.insert("const".to_string(), Const);
+    map.insert("var".to_string(), Var);
+    map.insert("as".to_string(), As);
+    map.insert("private".to_string(), Private);
+    map.insert("local".to_string(), Local);
     map
 });
 
@@ -43,6 +51,14 @@ pub enum Keyword {
     End,
     Call,
     CallIf,
+    Struct,
+    Let,
+    In,
+    Const,
+    Var,
+    As,
+    Private,
+    Local,
 }
 
 
@@ -114,13 +130,13 @@ impl From<(Position, String)> for Token {
                 let output = parts.get(1).unwrap().to_string();
 
                 let input = if input.len() > 0 {
-                    input.split(",").map(|inp| Types::from((str.clone().0, inp.to_string()))).collect::<Vec<Types>>()
+                    split_types(&input).into_iter().map(|inp| Types::from((str.clone().0, inp))).collect::<Vec<Types>>()
                 } else {
                     vec![]
                 };
 
                 let output = if output.len() > 0 {
-                    output.split(",").map(|inp| Types::from((str.clone().0, inp.to_string()))).collect::<Vec<Types>>()
+                    split_types(&output).into_iter().map(|inp| Types::from((str.clone().0, inp))).collect::<Vec<Types>>()
                 } else {
                     vec![]
                 };
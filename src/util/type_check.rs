@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::util::{compiler_error, compiler_error_str};
 use crate::util::operation::OperationDataInfo;
-use crate::util::operations::Descriptor as TDescriptor;
+use crate::util::operations::{descriptor_exists, Descriptor as TDescriptor};
 use crate::util::position::Position;
 
 static TYPES_MAP: SyncLazy<HashMap<String, Types>> = SyncLazy::new(|| {
@@ -19,7 +19,7 @@ static TYPES_MAP: SyncLazy<HashMap<String, Types>> = SyncLazy::new(|| {
     map.insert("bool".to_string(), Types::Bool);
     map.insert("ptr".to_string(), Types::Pointer);
     map.insert("fn".to_string(), Types::Function);
-    map.insert("rsc".to_string(), Types::Descriptor);
+    map.insert("rsc".to_string(), Types::Descriptor(None));
 
     map
 });
@@ -33,7 +33,11 @@ pub enum Types {
     Pointer,
     Function,
     FunctionPointer(Vec<Types>, Vec<Types>),
-    Descriptor,
+    /// A descriptor of the given kind. Without a kind any descriptor is accepted
+    Descriptor(Option<String>),
+    Map(Box<Types>, Box<Types>),
+    Struct(String),
+    Generic(String),
 }
 
 impl Into<String> for Types {
@@ -60,9 +64,23 @@ impl Into<String> for Types {
                 }).replacen(",", "", 1);
                 format!("fn-ptr({}->{})", inp, out)
             }
-            Types::Descriptor => {
+            Types::Descriptor(None) => {
                 "rsc".to_string()
             }
+            Types::Descriptor(Some(kind)) => {
+                format!("rsc<{}>", kind)
+            }
+            Types::Map(key, value) => {
+                let key: String = (*key).into();
+                let value: String = (*value).into();
+                format!("map<{},{}>", key, value)
+            }
+            Types::Struct(name) => {
+                name
+            }
+            Types::Generic(name) => {
+                format!("'{}", name)
+            }
         }
     }
 }
@@ -198,10 +216,206 @@ impl Display for ErrorTypes {
     }
 }
 
+impl Types {
+    pub fn is_map_key(&self) -> bool {
+        *self == Types::Int || *self == Types::String
+    }
+
+    /// Descriptors can not be copied, so types containing one can not be duplicated
+    pub fn contains_descriptor(&self) -> bool {
+        match self {
+            Types::Descriptor(_) => true,
+            Types::Map(key, value) => key.contains_descriptor() || value.contains_descriptor(),
+            _ => false,
+        }
+    }
+
+    /// Checks if a value of the type got can be used where this type is wanted
+    pub fn accepts(&self, got: &Types) -> bool {
+        match (self, got) {
+            (Types::Descriptor(None), Types::Descriptor(_)) => true,
+            (Types::Map(key, value), Types::Map(got_key, got_value)) => key.accepts(got_key) && value.accepts(got_value),
+            _ => self == got,
+        }
+    }
+
+    /// The type with every struct name replaced
+    pub fn rename_structs(&self, rename: &dyn Fn(&str) -> String) -> Types {
+        match self {
+            Types::Struct(name) => Types::Struct(rename(name)),
+            Types::Map(key, value) => Types::Map(Box::new(key.rename_structs(rename)), Box::new(value.rename_structs(rename))),
+            Types::FunctionPointer(inp, outp) => {
+                Types::FunctionPointer(inp.iter().map(|typ| typ.rename_structs(rename)).collect(), outp.iter().map(|typ| typ.rename_structs(rename)).collect())
+            }
+            typ => typ.clone(),
+        }
+    }
+
+    /// All struct names this type refers to
+    pub fn structs(&self) -> Vec<String> {
+        match self {
+            Types::Struct(name) => vec![name.clone()],
+            Types::Map(key, value) => {
+                let mut structs = key.structs();
+                structs.extend(value.structs());
+                structs
+            }
+            Types::FunctionPointer(inp, outp) => {
+                inp.iter().chain(outp.iter()).flat_map(|typ| typ.structs()).collect()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+fn unify(wanted: &Types, got: &Types, bindings: &mut HashMap<String, Types>) -> bool {
+    match wanted {
+        Types::Generic(name) => {
+            if let Some(bound) = bindings.get(name) {
+                bound == got
+            } else {
+                bindings.insert(name.clone(), got.clone());
+                true
+            }
+        }
+        Types::Map(key, value) => {
+            if let Types::Map(got_key, got_value) = got {
+                unify(key, got_key, bindings) && unify(value, got_value, bindings)
+            } else {
+                false
+            }
+        }
+        Types::FunctionPointer(inp, outp) => {
+            if let Types::FunctionPointer(got_inp, got_outp) = got {
+                inp.len() == got_inp.len() && outp.len() == got_outp.len()
+                    && inp.iter().zip(got_inp).all(|(wanted, got)| unify(wanted, got, bindings))
+                    && outp.iter().zip(got_outp).all(|(wanted, got)| unify(wanted, got, bindings))
+            } else {
+                false
+            }
+        }
+        _ => wanted.accepts(got)
+    }
+}
+
+fn substitute(typ: &Types, bindings: &HashMap<String, Types>) -> Types {
+    match typ {
+        Types::Generic(name) => bindings.get(name).cloned().unwrap_or_else(|| typ.clone()),
+        Types::Map(key, value) => Types::Map(Box::new(substitute(key, bindings)), Box::new(substitute(value, bindings))),
+        Types::FunctionPointer(inp, outp) => Types::FunctionPointer(
+            inp.iter().map(|typ| substitute(typ, bindings)).collect(),
+            outp.iter().map(|typ| substitute(typ, bindings)).collect(),
+        ),
+        _ => typ.clone()
+    }
+}
+
+/// Unifies the contract input with the top of the stack.
+/// On success the bound type variables are returned and the stack is left untouched.
+pub fn check_contract(inp: &Vec<Types>, stack: &Vec<Types>) -> Result<HashMap<String, Types>, TypeCheckError> {
+    if stack.len() < inp.len() {
+        return Err(ErrorTypes::TooFewElements.into_with_ctx(inp.clone(), stack.clone()));
+    }
+
+    let mut bindings = HashMap::new();
+    let top = &stack[stack.len() - inp.len()..];
+
+    if inp.iter().zip(top).all(|(wanted, got)| unify(wanted, got, &mut bindings)) {
+        Ok(bindings)
+    } else {
+        Err(ErrorTypes::InvalidTypes.into_with_ctx(inp.clone(), stack.clone()))
+    }
+}
+
+/// Counts how often a type variable appears in the types, including nested ones
+fn occurrences(types: &Vec<Types>, name: &str) -> usize {
+    types.iter().map(|typ| match typ {
+        Types::Generic(other) => (other == name) as usize,
+        Types::Map(key, value) => occurrences(&vec![*key.clone(), *value.clone()], name),
+        Types::FunctionPointer(inp, outp) => occurrences(inp, name) + occurrences(outp, name),
+        _ => 0,
+    }).sum()
+}
+
+/// Checks if a contract would duplicate a descriptor on the stack.
+/// This is the case if a type variable bound to a descriptor appears more often in the output than in the input
+pub fn copies_descriptor(inp: &Vec<Types>, outp: &Vec<Types>, stack: &Vec<Types>) -> bool {
+    check_contract(inp, stack).map_or(false, |bindings| {
+        bindings.iter().any(|(name, bound)| {
+            bound.contains_descriptor() && occurrences(outp, name) > occurrences(inp, name)
+        })
+    })
+}
+
+/// Replaces the contract input on the stack with its output, resolving type variables
+pub fn apply_contract(inp: &Vec<Types>, outp: &Vec<Types>, stack: &mut Vec<Types>) -> TypeCheckError {
+    match check_contract(inp, stack) {
+        Ok(bindings) => {
+            stack.truncate(stack.len() - inp.len());
+            stack.extend(outp.iter().map(|typ| substitute(typ, &bindings)));
+            ErrorTypes::None.into()
+        }
+        Err(err) => err
+    }
+}
+
+/// Splits a comma separated list of types while respecting nested '<' '>' pairs.
+/// "str,map<str,int>" -> ["str", "map<str,int>"]
+pub fn split_types(str: &str) -> Vec<String> {
+    let (mut parts, last, _) = str.chars().fold((vec![], String::new(), 0), |(mut parts, mut current, depth), char| {
+        let depth = match char {
+            '<' => depth + 1,
+            '>' => depth - 1,
+            ',' if depth == 0 => {
+                parts.push(current);
+                return (parts, String::new(), depth);
+            }
+            _ => depth,
+        };
+        current.push(char);
+        (parts, current, depth)
+    });
+
+    if last.len() > 0 {
+        parts.push(last);
+    }
+
+    parts
+}
+
 impl From<(Position, String)> for Types {
     fn from(token: (Position, String)) -> Self {
         if TYPES_MAP.contains_key(&token.1) {
             TYPES_MAP.get(&token.1).unwrap().clone()
+        } else if token.1.starts_with("map<") && token.1.ends_with(">") {
+            let inner = &token.1[4..token.1.len() - 1];
+            let params = split_types(inner);
+
+            if params.len() != 2 {
+                compiler_error(format!("A map needs exactly a key and a value type. Got: {}", token.1), &OperationDataInfo::Position(token.clone().0));
+            }
+
+            let key = Types::from((token.0.clone(), params.get(0).unwrap().clone()));
+            let value = Types::from((token.0.clone(), params.get(1).unwrap().clone()));
+
+            if !key.is_map_key() {
+                compiler_error(format!("Only int and str can be used as map keys. Got: {}", token.1), &OperationDataInfo::Position(token.clone().0));
+            }
+
+            Types::Map(Box::new(key), Box::new(value))
+        } else if token.1.starts_with("rsc<") && token.1.ends_with(">") {
+            let kind = &token.1[4..token.1.len() - 1];
+
+            if !descriptor_exists(kind) {
+                compiler_error(format!("The descriptor {} is not registered. Got: {}", kind, token.1), &OperationDataInfo::Position(token.clone().0));
+            }
+
+            Types::Descriptor(Some(kind.to_string()))
+        } else if token.1.starts_with("'") && token.1.len() > 1 && token.1[1..].chars().all(|char| char.is_ascii_alphanumeric()) {
+            Types::Generic(token.1[1..].to_string())
+        } else if token.1.rsplit("::").next().unwrap().starts_with(|char: char| char.is_ascii_uppercase()) {
+            // User defined structs start with an uppercase letter and are resolved after parsing. Structs of modules are named module::Name
+            Types::Struct(token.1)
         } else {
             compiler_error(format!("Invalid type: {}", token.1), &OperationDataInfo::Position(token.clone().0));
             unreachable!()
@@ -1,78 +1,72 @@
 use std::collections::HashMap;
 use std::process::exit;
+use std::rc::Rc;
+use std::sync::Mutex;
 
 use crate::CompiledProgram;
-use crate::parser::{Function, State};
+use crate::parser::{Function, State, VariableData};
 use crate::util::{compiler_error_str, runtime_error, runtime_error_str, runtime_warning, runtime_warning_str};
-use crate::util::operation::{Operation, OperationData, OperationDataInfo, OperationType};
+use crate::util::operation::{Operation, OperationData, OperationDataInfo, OperationType, TypeFrame};
+use crate::util::operations::{Descriptor, DescriptorAction};
 use crate::util::position::Position;
 use crate::util::register_type::RegisterType;
 use crate::util::type_check::{ErrorTypes, Types};
 
 pub const MAX_CALL_STACK_SIZE: u8 = 40;
+/// Registers a to h
+pub const REGISTER_COUNT: usize = 8;
 
 pub struct VM {
     ip: i32,
     ops: HashMap<String, Function>,
     stack: Vec<RegisterType>,
     type_stack: Vec<Types>,
+    locals: Vec<RegisterType>,
+    globals: HashMap<String, RegisterType>,
     last_op: Option<(OperationDataInfo, OperationData)>,
     depth: u8,
-    reg_a: RegisterType,
-    reg_b: RegisterType,
-    reg_c: RegisterType,
-    reg_d: RegisterType,
-    reg_e: RegisterType,
-    reg_f: RegisterType,
-    reg_g: RegisterType,
-    reg_h: RegisterType,
+    registers: Vec<RegisterType>,
+    /// The types of the registers and locals, checked like at compile time
+    type_frame: TypeFrame,
+    descriptors: Vec<Rc<Mutex<Box<dyn Descriptor>>>>,
+    /// The command line arguments of the program
+    args: Vec<String>,
 }
 
 impl From<State> for VM {
     fn from(state: State) -> Self {
         let mut ops = state.get_ops().clone();
-        VM::new(ops)
+        VM::new(ops, state.get_variables())
     }
 }
 
 impl From<CompiledProgram> for VM {
     fn from(program: CompiledProgram) -> Self {
         let fncs = program.operations;
-        let fncs = fncs.iter().map(|entry| {
-            let fnc = entry.1;
-            let data = fnc.data.clone();
-            let fnc_ops = fnc.operations.iter().map(|op| {
-                let nop = Operation::from(op.1.clone());
-                (op.0.clone(), nop)
-            }).collect::<Vec<_>>();
-
-            let fnc = Function {
-                data,
-                operations: fnc_ops,
-            };
-            (entry.0.clone(), fnc)
-        }).collect::<HashMap<_, _>>();
-        VM::new(fncs)
+        let fncs = fncs.into_iter().map(|(name, fnc)| (name, Function::from(fnc))).collect::<HashMap<_, _>>();
+        VM::new(fncs, &program.variables)
     }
 }
 
 impl VM {
-    pub fn new(ops: HashMap<String, Function>) -> Self {
+    pub fn new(ops: HashMap<String, Function>, variables: &HashMap<String, VariableData>) -> Self {
+        let globals = variables.iter().map(|(name, variable)| {
+            (name.clone(), RegisterType::from_operand(variable.initial().clone()).unwrap())
+        }).collect();
+
         Self {
             ip: 0,
             ops,
             stack: vec![],
             type_stack: vec![],
+            locals: vec![],
+            globals,
             last_op: None,
             depth: 0,
-            reg_a: RegisterType::Empty,
-            reg_b: RegisterType::Empty,
-            reg_c: RegisterType::Empty,
-            reg_d: RegisterType::Empty,
-            reg_e: RegisterType::Empty,
-            reg_f: RegisterType::Empty,
-            reg_g: RegisterType::Empty,
-            reg_h: RegisterType::Empty,
+            registers: vec![RegisterType::Empty; REGISTER_COUNT],
+            type_frame: TypeFrame::new(),
+            descriptors: vec![],
+            args: vec![],
         }
     }
 
@@ -87,6 +81,8 @@ impl VM {
 
         self.execute_fn(&start);
 
+        self.warn_open_descriptors();
+
         if self.stack.len() != 1 {
             runtime_error_str("No return code provided", &empty);
         }
@@ -98,12 +94,40 @@ impl VM {
         }
     }
 
+    /// Descriptors should be closed explicitly. The ones left open by main are reported
+    fn warn_open_descriptors(&mut self) {
+        let empty = OperationDataInfo::None;
+
+        for descriptor in std::mem::take(&mut self.descriptors) {
+            let mut lock = descriptor.lock();
+            let lock = lock.as_mut().unwrap();
+            if lock.is_open() {
+                let mut name = vec![];
+                lock.action(DescriptorAction::ToString, &mut name, &empty);
+                let name = name.pop().and_then(|name| name.to_string(&empty)).unwrap_or_default();
+                runtime_warning(format!("The descriptor {} was not closed", name), &empty);
+            }
+        }
+    }
+
     pub fn execute_fn(&mut self, fnc: &Function) {
+        // Local registers start empty and are restored after the call. The others keep their value across calls
+        let saved = fnc.local_registers.iter().map(|register| {
+            let value = std::mem::replace(&mut self.registers[*register], RegisterType::Empty);
+            let typ = self.type_frame.registers[*register].take();
+            (*register, value, typ)
+        }).collect::<Vec<_>>();
+
         self.depth += 1;
         for operation in &fnc.operations {
             self.execute_op(operation, fnc.name())
         }
         self.depth -= 1;
+
+        for (register, value, typ) in saved {
+            self.registers[register] = value;
+            self.type_frame.registers[register] = typ;
+        }
     }
 
     pub fn stack(&self) -> &Vec<RegisterType> {
@@ -114,10 +138,38 @@ impl VM {
         &mut self.stack
     }
 
+    pub fn type_stack_mut(&mut self) -> &mut Vec<Types> {
+        &mut self.type_stack
+    }
+
+    pub fn globals_mut(&mut self) -> &mut HashMap<String, RegisterType> {
+        &mut self.globals
+    }
+
+    pub fn registers_mut(&mut self) -> &mut Vec<RegisterType> {
+        &mut self.registers
+    }
+
+    pub fn descriptors_mut(&mut self) -> &mut Vec<Rc<Mutex<Box<dyn Descriptor>>>> {
+        &mut self.descriptors
+    }
+
+    pub fn args(&self) -> &Vec<String> {
+        &self.args
+    }
+
+    pub fn set_args(&mut self, args: Vec<String>) {
+        self.args = args;
+    }
+
+    /// Values bound by let. Every scope is closed before its function returns
+    pub fn locals_mut(&mut self) -> &mut Vec<RegisterType> {
+        &mut self.locals
+    }
+
     fn execute_op(&mut self, op: &(OperationDataInfo, Operation), fn_name: String) {
         let info = &op.0;
         let data = op.1.data();
-        let typecheck = &op.1.type_check;
         let exec = &op.1.execute_fn;
         if self.depth > MAX_CALL_STACK_SIZE {
             runtime_error_str("Stack overflow", info);
@@ -132,7 +184,7 @@ impl VM {
             }
         }
 
-        let tc_error = (typecheck(data, &self.ops, &mut self.type_stack, false)).is_error();
+        let tc_error = op.1.type_check_with_frame(&self.ops, &mut self.type_stack, &mut self.type_frame, false).is_error();
 
         if !tc_error {
             exec(data, self);